@@ -0,0 +1,230 @@
+//! Headless `--convert` mode: runs a single conversion through the normal
+//! `ConversionManager`/`queue_conversion` pipeline without ever creating a
+//! window, for scripted use (`frame --convert in.mkv --preset "Web 1080p"
+//! --output out.mp4`).
+
+use serde::Deserialize;
+use tauri::{Listener, Manager};
+
+use crate::conversion::{ConversionConfig, ConversionManager, EncoderCache, queue_conversion};
+
+/// Parsed form of the `--convert`/`--output`/`--preset`/`--config`/`--json`
+/// launch arguments. `None` from [`parse_args`] means `--convert` wasn't
+/// present at all, i.e. the GUI should start normally.
+pub(crate) struct CliArgs {
+    input: String,
+    output: Option<String>,
+    preset: Option<String>,
+    config_path: Option<String>,
+    json: bool,
+}
+
+/// Scans the process argv for `--convert` and its companions. Only present
+/// when the app was invoked for headless conversion; any other flag is left
+/// for the GUI/frontend to interpret.
+pub(crate) fn parse_args(argv: &[String]) -> Option<CliArgs> {
+    let mut input = None;
+    let mut output = None;
+    let mut preset = None;
+    let mut config_path = None;
+    let mut json = false;
+
+    let mut iter = argv.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--convert" => input = iter.next().cloned(),
+            "--output" => output = iter.next().cloned(),
+            "--preset" => preset = iter.next().cloned(),
+            "--config" => config_path = iter.next().cloned(),
+            "--json" => json = true,
+            _ => {}
+        }
+    }
+
+    input.map(|input| CliArgs {
+        input,
+        output,
+        preset,
+        config_path,
+        json,
+    })
+}
+
+#[derive(Deserialize)]
+struct PresetDefinition {
+    name: String,
+    config: ConversionConfig,
+}
+
+#[derive(Deserialize)]
+struct PresetStore {
+    presets: Vec<PresetDefinition>,
+}
+
+/// Looks a named preset up in the same `presets.dat` store the frontend
+/// reads and writes via `tauri-plugin-store`.
+fn load_preset_config(app: &tauri::AppHandle, name: &str) -> Result<ConversionConfig, String> {
+    let store_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("presets.dat");
+
+    let contents = std::fs::read_to_string(&store_path)
+        .map_err(|e| format!("Failed to read {}: {}", store_path.display(), e))?;
+    let store: PresetStore =
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid presets file: {}", e))?;
+
+    store
+        .presets
+        .into_iter()
+        .find(|preset| preset.name == name)
+        .map(|preset| preset.config)
+        .ok_or_else(|| format!("No preset named \"{}\" was found", name))
+}
+
+fn resolve_config(app: &tauri::AppHandle, args: &CliArgs) -> Result<ConversionConfig, String> {
+    if let Some(config_path) = &args.config_path {
+        let contents = std::fs::read_to_string(config_path)
+            .map_err(|e| format!("Failed to read {}: {}", config_path, e))?;
+        return serde_json::from_str(&contents).map_err(|e| format!("Invalid config file: {}", e));
+    }
+
+    if let Some(preset) = &args.preset {
+        return load_preset_config(app, preset);
+    }
+
+    Err("--convert requires either --preset <name> or --config <path>".to_string())
+}
+
+#[derive(Deserialize)]
+struct CliProgressEvent {
+    id: String,
+    progress: f64,
+    fps: Option<f64>,
+    speed: Option<f64>,
+    eta_seconds: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct CliCompletedEvent {
+    id: String,
+    output_path: String,
+}
+
+#[derive(Deserialize)]
+struct CliErrorEvent {
+    id: String,
+    error: String,
+}
+
+/// Runs a single headless conversion to completion and returns the process
+/// exit code: `0` on success, non-zero if the input was invalid or the
+/// conversion failed.
+pub(crate) fn run_headless(args: CliArgs) -> i32 {
+    let app = tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            app.manage(EncoderCache::default());
+            app.manage(ConversionManager::new(app.handle().clone()));
+            Ok(())
+        })
+        .build(tauri::generate_context!())
+        .expect("error while starting headless conversion");
+    let handle = app.handle().clone();
+
+    let config = match resolve_config(&handle, &args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    let (result_tx, result_rx) = std::sync::mpsc::channel::<Result<String, String>>();
+    let task_id = format!("cli-{}", std::process::id());
+    let json = args.json;
+
+    let progress_task_id = task_id.clone();
+    handle.listen("conversion-progress", move |event| {
+        let Ok(payload) = serde_json::from_str::<CliProgressEvent>(event.payload()) else {
+            return;
+        };
+        if payload.id != progress_task_id {
+            return;
+        }
+        if json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "event": "progress",
+                    "id": payload.id,
+                    "progress": payload.progress,
+                    "fps": payload.fps,
+                    "speed": payload.speed,
+                    "etaSeconds": payload.eta_seconds,
+                })
+            );
+        } else {
+            print!("\rConverting... {:.1}%", payload.progress);
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+        }
+    });
+
+    let completed_task_id = task_id.clone();
+    let completed_tx = result_tx.clone();
+    handle.listen("conversion-completed", move |event| {
+        let Ok(payload) = serde_json::from_str::<CliCompletedEvent>(event.payload()) else {
+            return;
+        };
+        if payload.id == completed_task_id {
+            let _ = completed_tx.send(Ok(payload.output_path));
+        }
+    });
+
+    let error_task_id = task_id.clone();
+    let error_tx = result_tx;
+    handle.listen("conversion-error", move |event| {
+        let Ok(payload) = serde_json::from_str::<CliErrorEvent>(event.payload()) else {
+            return;
+        };
+        if payload.id == error_task_id {
+            let _ = error_tx.send(Err(payload.error));
+        }
+    });
+
+    let manager = handle.state::<ConversionManager>();
+    let encoder_cache = handle.state::<EncoderCache>();
+    let enqueue_result = tauri::async_runtime::block_on(queue_conversion(
+        manager,
+        encoder_cache,
+        task_id,
+        args.input,
+        args.output,
+        config,
+        None,
+        None,
+    ));
+
+    if let Err(e) = enqueue_result {
+        eprintln!("Failed to queue conversion: {}", e);
+        return 1;
+    }
+
+    match result_rx.recv() {
+        Ok(Ok(output_path)) => {
+            if !json {
+                println!("\nDone: {}", output_path);
+            }
+            0
+        }
+        Ok(Err(error)) => {
+            eprintln!("\nConversion failed: {}", error);
+            1
+        }
+        Err(_) => {
+            eprintln!("\nConversion channel closed unexpectedly");
+            1
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Bundles everything needed to debug a bug report into a single zip: sidecar
+//! version info, current settings, the live queue snapshot, recent history,
+//! this session's logs for the most recently finished tasks, and system
+//! info. Built entirely with the `zip` crate rather than shelling out to
+//! `zip`/`tar`, since those aren't guaranteed to exist on the user's machine.
+
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+use crate::conversion::{
+    ConversionError, ConversionManager, EventThrottleSettings, NotificationPreferences,
+    OutputSettings, QueueCompleteAction, QueueStateSnapshot, SidecarStatus, SidecarStatusCache,
+    StallWatchdogSettings, check_sidecars,
+};
+use crate::system_info::{OsSystemInfoProbe, SystemInfo, build_system_info};
+
+/// How many of the most recent history entries (and, of those, how many
+/// still have an in-memory log from this session) get bundled — enough to
+/// debug a recent failure without the archive growing unbounded.
+const DIAGNOSTIC_HISTORY_COUNT: usize = 50;
+const DIAGNOSTIC_TASK_LOG_COUNT: usize = 10;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsSnapshot {
+    max_concurrency: usize,
+    default_threads: Option<u32>,
+    background_priority: bool,
+    keep_partial_on_error: bool,
+    disk_space_check: bool,
+    fill_paused_slots: bool,
+    on_queue_complete_action: QueueCompleteAction,
+    skip_power_action_if_all_failed: bool,
+    notification_preferences: NotificationPreferences,
+    stall_watchdog_settings: StallWatchdogSettings,
+    output_settings: OutputSettings,
+    mirror_logs_to_disk: bool,
+    include_failed_outputs_in_orphan_scan: bool,
+    event_throttle: EventThrottleSettings,
+}
+
+fn settings_snapshot(manager: &ConversionManager) -> SettingsSnapshot {
+    SettingsSnapshot {
+        max_concurrency: manager.current_max_concurrency(),
+        default_threads: manager.current_default_threads(),
+        background_priority: manager.current_background_priority(),
+        keep_partial_on_error: manager.current_keep_partial_on_error(),
+        disk_space_check: manager.current_disk_space_check(),
+        fill_paused_slots: manager.current_fill_paused_slots(),
+        on_queue_complete_action: manager.current_on_queue_complete_action(),
+        skip_power_action_if_all_failed: manager.current_skip_power_action_if_all_failed(),
+        notification_preferences: manager.current_notification_preferences(),
+        stall_watchdog_settings: manager.current_stall_watchdog_settings(),
+        output_settings: manager.current_output_settings(),
+        mirror_logs_to_disk: manager.current_mirror_logs_to_disk(),
+        include_failed_outputs_in_orphan_scan: manager
+            .current_include_failed_outputs_in_orphan_scan(),
+        event_throttle: manager.current_event_throttle(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiagnosticBundleResult {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+fn home_dir() -> Option<String> {
+    std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()
+        .filter(|home| !home.is_empty())
+}
+
+/// Replaces every occurrence of `home` with a placeholder, so a bug report
+/// doesn't leak the reporter's username via file paths embedded in settings,
+/// history, or log lines.
+fn redact(text: String, home: Option<&str>) -> String {
+    match home {
+        Some(home) => text.replace(home, "<home>"),
+        None => text,
+    }
+}
+
+fn to_json(value: &impl Serialize) -> Result<String, ConversionError> {
+    serde_json::to_string_pretty(value).map_err(ConversionError::from)
+}
+
+/// Builds the archive's file list as `(name, contents)` pairs, applying
+/// redaction uniformly across every entry rather than field-by-field, so a
+/// path embedded anywhere (a settings field, a history entry, a raw ffmpeg
+/// stderr line) is scrubbed the same way.
+async fn collect_entries(
+    app: &AppHandle,
+    manager: &ConversionManager,
+    sidecar_cache: tauri::State<'_, SidecarStatusCache>,
+    home: Option<&str>,
+) -> Result<Vec<(String, String)>, ConversionError> {
+    let mut entries = Vec::new();
+
+    let sidecars: SidecarStatus = check_sidecars(app.clone(), sidecar_cache).await?;
+    entries.push(("sidecars.json".to_string(), to_json(&sidecars)?));
+
+    entries.push((
+        "settings.json".to_string(),
+        to_json(&settings_snapshot(manager))?,
+    ));
+
+    let queue: QueueStateSnapshot = manager.get_queue_state().await?;
+    entries.push(("queue.json".to_string(), to_json(&queue)?));
+
+    let history = manager
+        .get_conversion_history(DIAGNOSTIC_HISTORY_COUNT, 0)
+        .await?;
+    entries.push(("history.json".to_string(), to_json(&history)?));
+
+    for task in history.iter().take(DIAGNOSTIC_TASK_LOG_COUNT) {
+        if let Ok(lines) = manager.get_task_log(&task.id) {
+            entries.push((format!("logs/{}.log", task.id), lines.join("\n")));
+        }
+    }
+
+    let output_dir = manager.current_output_settings().output_directory;
+    let system_info: SystemInfo = build_system_info(&OsSystemInfoProbe, output_dir);
+    entries.push(("system_info.json".to_string(), to_json(&system_info)?));
+
+    Ok(entries
+        .into_iter()
+        .map(|(name, contents)| (name, redact(contents, home)))
+        .collect())
+}
+
+fn write_zip(dest_path: &Path, entries: &[(String, String)]) -> Result<u64, ConversionError> {
+    let file = std::fs::File::create(dest_path)?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, contents) in entries {
+        zip.start_file(name, options)
+            .map_err(|e| ConversionError::Shell(e.to_string()))?;
+        zip.write_all(contents.as_bytes())?;
+    }
+    zip.finish()
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+
+    Ok(std::fs::metadata(dest_path).map(|m| m.len()).unwrap_or(0))
+}
+
+/// Collects diagnostics and writes them to `dest_path` as a zip, returning
+/// its final path and size. Set `redact_paths` before attaching the result
+/// to a public bug report.
+#[tauri::command]
+pub(crate) async fn export_diagnostics(
+    app: AppHandle,
+    manager: tauri::State<'_, ConversionManager>,
+    sidecar_cache: tauri::State<'_, SidecarStatusCache>,
+    dest_path: String,
+    redact_paths: Option<bool>,
+) -> Result<DiagnosticBundleResult, ConversionError> {
+    let home = if redact_paths.unwrap_or(false) {
+        home_dir()
+    } else {
+        None
+    };
+
+    let entries = collect_entries(&app, &manager, sidecar_cache, home.as_deref()).await?;
+    let size_bytes = write_zip(Path::new(&dest_path), &entries)?;
+
+    Ok(DiagnosticBundleResult {
+        path: dest_path,
+        size_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_replaces_every_occurrence_of_home() {
+        let text = "input: /home/alice/movie.mp4\noutput: /home/alice/out/movie.mp4".to_string();
+        let redacted = redact(text, Some("/home/alice"));
+        assert_eq!(
+            redacted,
+            "input: <home>/movie.mp4\noutput: <home>/out/movie.mp4"
+        );
+    }
+
+    #[test]
+    fn test_redact_is_a_no_op_without_a_home_dir() {
+        let text = "input: /home/alice/movie.mp4".to_string();
+        assert_eq!(redact(text.clone(), None), text);
+    }
+}
@@ -0,0 +1,870 @@
+//! Rough output-size estimation for the configured encode settings, based on
+//! per-codec bits-per-pixel curves rather than an actual trial encode.
+
+use serde::Serialize;
+
+use crate::conversion::{
+    crf_range_for_codec, effective_trim_duration, trim_duration, uses_quality_field,
+    ConversionConfig, ConversionError, ConversionManager, Resolution,
+};
+use crate::media::{metadata_dimensions, parse_frame_rate_string, ProbeMetadata};
+
+/// A codec's quality curve: the bits-per-pixel figure at a "reference" CRF/quality
+/// value, used to scale estimates up or down from that reference point. For a
+/// codec in `uses_quality_field`, `reference_crf` is on that codec's own
+/// constant-quality scale (see `effective_crf`), not a literal `-crf` value.
+pub struct CodecReference {
+    pub reference_crf: f64,
+    pub bits_per_pixel_at_reference: f64,
+}
+
+/// Returns the bitrate reference curve for a given video codec, falling back to the
+/// x264 curve for codecs we don't have dedicated data for. Hardware encoders
+/// generally spend more bits than their software counterparts at an
+/// equivalent quality setting, and lose further ground from h264 to hevc to
+/// av1 as the codec's own compression efficiency improves but the encoder's
+/// rate-distortion search stays comparatively simple.
+pub fn codec_reference(video_codec: &str) -> CodecReference {
+    match video_codec {
+        "libx265" => CodecReference {
+            reference_crf: 28.0,
+            bits_per_pixel_at_reference: 0.04,
+        },
+        "libsvtav1" | "libaom-av1" => CodecReference {
+            reference_crf: 32.0,
+            bits_per_pixel_at_reference: 0.03,
+        },
+        "libvpx-vp9" => CodecReference {
+            reference_crf: 31.0,
+            bits_per_pixel_at_reference: 0.035,
+        },
+
+        // nvenc/vaapi/amf share the (52 - quality/2) cq/qp scale (see
+        // `effective_crf_for_quality`), so their reference point lines up
+        // with `default_quality`'s cq/qp of 27.
+        "h264_nvenc" => CodecReference {
+            reference_crf: 27.0,
+            bits_per_pixel_at_reference: 0.09,
+        },
+        "hevc_nvenc" => CodecReference {
+            reference_crf: 27.0,
+            bits_per_pixel_at_reference: 0.05,
+        },
+        "av1_nvenc" => CodecReference {
+            reference_crf: 27.0,
+            bits_per_pixel_at_reference: 0.035,
+        },
+        "h264_vaapi" => CodecReference {
+            reference_crf: 27.0,
+            bits_per_pixel_at_reference: 0.10,
+        },
+        "hevc_vaapi" => CodecReference {
+            reference_crf: 27.0,
+            bits_per_pixel_at_reference: 0.06,
+        },
+        "av1_vaapi" => CodecReference {
+            reference_crf: 27.0,
+            bits_per_pixel_at_reference: 0.045,
+        },
+        "h264_amf" => CodecReference {
+            reference_crf: 27.0,
+            bits_per_pixel_at_reference: 0.10,
+        },
+        "hevc_amf" => CodecReference {
+            reference_crf: 27.0,
+            bits_per_pixel_at_reference: 0.06,
+        },
+        "av1_amf" => CodecReference {
+            reference_crf: 27.0,
+            bits_per_pixel_at_reference: 0.045,
+        },
+
+        // qsv passes `quality` straight through as `-global_quality`, and
+        // videotoolbox inverts it (see `effective_crf_for_quality`); both
+        // scales put `default_quality`'s 50 at reference.
+        "h264_qsv" => CodecReference {
+            reference_crf: 50.0,
+            bits_per_pixel_at_reference: 0.095,
+        },
+        "hevc_qsv" => CodecReference {
+            reference_crf: 50.0,
+            bits_per_pixel_at_reference: 0.055,
+        },
+        "av1_qsv" => CodecReference {
+            reference_crf: 50.0,
+            bits_per_pixel_at_reference: 0.04,
+        },
+        "h264_videotoolbox" => CodecReference {
+            reference_crf: 50.0,
+            bits_per_pixel_at_reference: 0.09,
+        },
+        "hevc_videotoolbox" => CodecReference {
+            reference_crf: 50.0,
+            bits_per_pixel_at_reference: 0.05,
+        },
+
+        _ => CodecReference {
+            reference_crf: 23.0,
+            bits_per_pixel_at_reference: 0.07,
+        },
+    }
+}
+
+/// Converts `config.quality` (1-100, higher is better) into a value on the
+/// same lower-is-better scale `reference_crf` is expressed on for codecs in
+/// `uses_quality_field`, mirroring the transforms `build_ffmpeg_args` applies
+/// to each: nvenc/vaapi/amf all derive a 1-51 cq/qp from the same
+/// `52 - quality/2` formula, qsv passes `quality` straight through as its
+/// `-global_quality`, and videotoolbox's `-q:v` runs the opposite direction
+/// (higher is better), so it's mirrored around 100.
+fn effective_crf_for_quality(video_codec: &str, quality: u32) -> f64 {
+    if video_codec.contains("nvenc") || video_codec.contains("vaapi") || video_codec.contains("amf")
+    {
+        (52.0 - quality as f64 / 2.0).clamp(1.0, 51.0)
+    } else if video_codec.contains("videotoolbox") {
+        100.0 - quality as f64
+    } else {
+        quality as f64
+    }
+}
+
+/// The CRF/quality-equivalent value to scale `codec_reference`'s curve from:
+/// `config.crf` for codecs that use it, or `config.quality` converted onto
+/// the same scale for the ones that don't (see `effective_crf_for_quality`).
+fn effective_crf(config: &ConversionConfig) -> f64 {
+    if uses_quality_field(&config.video_codec) {
+        effective_crf_for_quality(&config.video_codec, config.quality)
+    } else {
+        config.crf as f64
+    }
+}
+
+/// Resolves the resolution preset/custom size against the source dimensions,
+/// applying the same never-upscale rule as `build_ffmpeg_args`'s scale filter
+/// so size estimates match what ffmpeg will actually produce.
+pub fn determine_target_dimensions(
+    config: &ConversionConfig,
+    source_width: u32,
+    source_height: u32,
+) -> (u32, u32) {
+    let resolution = Resolution::from_config(config).unwrap_or(Resolution::Original);
+    let (mut target_w, mut target_h): (i64, i64) = match resolution {
+        Resolution::Original => return (source_width, source_height),
+        Resolution::Custom { width, height } => (width as i64, height as i64),
+        preset => (-1, preset.preset_height().unwrap_or(-1)),
+    };
+
+    if target_w == -1 && target_h != -1 {
+        target_w = (source_width as i64 * target_h) / (source_height.max(1) as i64);
+    } else if target_h == -1 && target_w != -1 {
+        target_h = (source_height as i64 * target_w) / (source_width.max(1) as i64);
+    }
+
+    let mut width = target_w.max(1) as u32;
+    let mut height = target_h.max(1) as u32;
+
+    if !config.allow_upscale {
+        width = width.min(source_width);
+        height = height.min(source_height);
+    }
+
+    (width, height)
+}
+
+/// Resolves the configured fps setting to a concrete frame rate for
+/// estimation purposes, falling back to `source_fps` for "original" or an
+/// unparseable value.
+pub fn determine_target_fps(config: &ConversionConfig, source_fps: f64) -> f64 {
+    if config.fps == "original" {
+        return source_fps;
+    }
+    parse_frame_rate_string(&config.fps).unwrap_or(source_fps)
+}
+
+/// Estimates the output video bitrate in kbps for CRF/quality-mode encodes by
+/// scaling a codec's reference bits-per-pixel figure relative to the configured CRF.
+pub fn estimate_quality_video_bitrate(
+    config: &ConversionConfig,
+    width: u32,
+    height: u32,
+    fps: f64,
+) -> f64 {
+    let reference = codec_reference(&config.video_codec);
+    let crf_delta = effective_crf(config) - reference.reference_crf;
+    // Each +6 CRF roughly halves the bitrate; each -6 roughly doubles it.
+    let scale = 2f64.powf(-crf_delta / 6.0);
+    let bits_per_pixel = reference.bits_per_pixel_at_reference * scale;
+
+    (width as f64) * (height as f64) * fps * bits_per_pixel / 1000.0
+}
+
+/// Rough multiplier applied to the source bitrate when estimating a lossless
+/// encode's size: lossless re-encodes rarely shrink much, so this scales the
+/// source bitrate directly rather than reading anything off a CRF curve.
+const LOSSLESS_BITRATE_MULTIPLIER: f64 = 1.0;
+
+/// Estimates the output video bitrate in kbps, using the codec's CRF/quality
+/// curve normally, or a rough multiplier on `source_bitrate_kbps` when
+/// `config.lossless` is set. Lossless bypasses CRF/quality entirely, so the
+/// curve-based estimate doesn't apply there and this number is only a rough
+/// approximation, not a curve-fitted one. Stream-copy ("copy") video is
+/// neither: it's an exact passthrough of the source bitstream, so the CRF
+/// curve would be actively wrong and the configured resolution/fps (invalid
+/// settings for copy anyway) are ignored in favor of `source_bitrate_kbps`
+/// verbatim, including the "unknown" case where that's 0. Bitrate mode is
+/// directly controlled by the encoder's rate control, so it reads
+/// `config.video_bitrate` verbatim rather than going through the CRF/quality
+/// curve.
+pub fn estimate_output_video_bitrate(
+    config: &ConversionConfig,
+    width: u32,
+    height: u32,
+    fps: f64,
+    source_bitrate_kbps: f64,
+) -> f64 {
+    if config.video_codec == "copy" {
+        return source_bitrate_kbps;
+    }
+    if config.lossless {
+        return source_bitrate_kbps * LOSSLESS_BITRATE_MULTIPLIER;
+    }
+    if config.video_bitrate_mode == "bitrate" {
+        return config.video_bitrate.parse::<f64>().unwrap_or(0.0);
+    }
+    estimate_quality_video_bitrate(config, width, height, fps)
+}
+
+/// Predicts the total output file size in bytes for a conversion, combining
+/// the estimated video bitrate with the configured audio bitrate over the
+/// source duration. Used as a pre-flight check against available disk space,
+/// so it deliberately leans on the same rough curves as the UI's estimate
+/// rather than requiring a trial encode.
+pub fn estimate_output_size_bytes(
+    config: &ConversionConfig,
+    source_width: u32,
+    source_height: u32,
+    source_fps: f64,
+    source_bitrate_kbps: f64,
+    duration_secs: f64,
+) -> u64 {
+    let (width, height) = determine_target_dimensions(config, source_width, source_height);
+    let fps = determine_target_fps(config, source_fps);
+    let video_kbps = estimate_output_video_bitrate(config, width, height, fps, source_bitrate_kbps);
+    let audio_kbps = config.audio_bitrate.parse::<f64>().unwrap_or(0.0);
+
+    (((video_kbps + audio_kbps) * 1000.0 / 8.0) * duration_secs.max(0.0)) as u64
+}
+
+/// How far the estimate is allowed to drift, as a fraction of the midpoint,
+/// before `estimate_output` calls it "high"/"medium"/"low" confidence.
+const HIGH_CONFIDENCE_VARIANCE: f64 = 0.05;
+const MEDIUM_CONFIDENCE_VARIANCE: f64 = 0.25;
+
+/// Estimates how much a CRF/quality-mode estimate can drift from the actual
+/// encode, as a fraction of the midpoint: bitrate mode is tightly controlled
+/// by the encoder's rate control, so it starts narrow, while CRF/quality mode
+/// has no such guarantee and starts wider. Either mode widens further when
+/// the source bitrate (used to calibrate `lossless`/`copy` estimates) wasn't
+/// available from the probe.
+fn estimate_variance_fraction(config: &ConversionConfig, metadata: &ProbeMetadata) -> f64 {
+    let mode_fraction = if config.video_bitrate_mode == "bitrate" {
+        0.02
+    } else {
+        0.20
+    };
+    let missing_source_bitrate_penalty = if metadata.video_bitrate_kbps.is_none() {
+        0.25
+    } else {
+        0.0
+    };
+    mode_fraction + missing_source_bitrate_penalty
+}
+
+fn confidence_label(variance_fraction: f64) -> String {
+    if variance_fraction <= HIGH_CONFIDENCE_VARIANCE {
+        "high"
+    } else if variance_fraction <= MEDIUM_CONFIDENCE_VARIANCE {
+        "medium"
+    } else {
+        "low"
+    }
+    .to_string()
+}
+
+/// A predicted output size with a confidence range instead of a single
+/// number, since the curve-based estimate can be off by a lot for CRF/quality
+/// mode or when the source bitrate is unknown. `size_mb` is the midpoint, for
+/// callers that just want one number.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputEstimate {
+    pub size_mb: f64,
+    pub size_mb_low: f64,
+    pub size_mb_high: f64,
+    pub confidence: String,
+    /// Whether a learned per-codec correction factor (see
+    /// `ConversionManager::get_estimation_calibration`) was folded into
+    /// `size_mb`, or the curve's raw midpoint was used as-is because no
+    /// completed conversions have been recorded for this codec yet.
+    pub calibration_applied: bool,
+}
+
+/// The actual math behind `estimate_output`, split out so it stays
+/// unit-testable without a running `ConversionManager`: callers that already
+/// know the calibration factor (or want to test the uncalibrated curve) can
+/// call this directly.
+fn estimate_output_calibrated(
+    config: &ConversionConfig,
+    metadata: &ProbeMetadata,
+    calibration_factor: Option<f64>,
+) -> Result<OutputEstimate, ConversionError> {
+    let duration_secs = metadata
+        .duration
+        .as_deref()
+        .and_then(|d| d.parse::<f64>().ok())
+        .ok_or_else(|| ConversionError::InvalidInput("Missing probed duration".to_string()))?;
+    let (width, height) = metadata_dimensions(metadata);
+    let source_fps = metadata.frame_rate.unwrap_or(30.0);
+    let source_bitrate_kbps = metadata.video_bitrate_kbps.unwrap_or(0.0);
+
+    let size_bytes = estimate_output_size_bytes(
+        config,
+        width,
+        height,
+        source_fps,
+        source_bitrate_kbps,
+        duration_secs,
+    );
+    let size_mb = size_bytes as f64 / (1024.0 * 1024.0) * calibration_factor.unwrap_or(1.0);
+    let variance_fraction = estimate_variance_fraction(config, metadata);
+
+    Ok(OutputEstimate {
+        size_mb,
+        size_mb_low: size_mb * (1.0 - variance_fraction),
+        size_mb_high: size_mb * (1.0 + variance_fraction),
+        confidence: confidence_label(variance_fraction),
+        calibration_applied: calibration_factor.is_some(),
+    })
+}
+
+/// Estimates `config`'s output size for a file already probed into
+/// `metadata`, with a confidence range reflecting how much the bitrate mode
+/// and missing metadata could make the real result drift from the midpoint.
+/// Applies a learned per-codec correction factor on top of the curve-based
+/// midpoint when completed conversions have taught one (see
+/// `get_estimation_calibration`).
+#[tauri::command]
+pub async fn estimate_output(
+    manager: tauri::State<'_, ConversionManager>,
+    config: ConversionConfig,
+    metadata: ProbeMetadata,
+) -> Result<OutputEstimate, ConversionError> {
+    let calibration_factor = manager
+        .get_estimation_calibration()
+        .await?
+        .get(&config.video_codec)
+        .copied();
+    estimate_output_calibrated(&config, &metadata, calibration_factor)
+}
+
+/// Below this video bitrate, a bitrate-mode encode looks bad regardless of
+/// resolution or codec, so a target size requiring less than this is flagged
+/// as not achievable rather than silently suggesting it.
+const MIN_ACHIEVABLE_VIDEO_KBPS: f64 = 100.0;
+
+/// A suggested setting to hit `target_mb`, computed by inverting whichever of
+/// the two estimation paths `config.video_bitrate_mode` is already using.
+/// Only the field matching that mode is populated: a bitrate-mode config gets
+/// `suggested_bitrate_kbps`, everything else (CRF/quality) gets
+/// `suggested_crf` (on `config.video_codec`'s own CRF/quality scale, see
+/// `estimation::effective_crf`).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeSuggestion {
+    pub suggested_bitrate_kbps: Option<f64>,
+    pub suggested_crf: Option<f64>,
+    pub achievable: bool,
+    pub note: String,
+}
+
+/// Suggests the video bitrate (bitrate mode) or CRF/quality value (CRF mode)
+/// that would land `config`'s output near `target_mb`, accounting for the
+/// configured audio bitrate and trim. Built by inverting
+/// `estimate_output_size_bytes`'s math rather than adding a new model, so a
+/// round-trip through `estimate_output` with the suggested setting lands
+/// close to `target_mb`.
+#[tauri::command]
+pub fn suggest_settings_for_size(
+    metadata: ProbeMetadata,
+    config: ConversionConfig,
+    target_mb: f64,
+) -> Result<SizeSuggestion, ConversionError> {
+    let source_duration = metadata
+        .duration
+        .as_deref()
+        .and_then(|d| d.parse::<f64>().ok())
+        .ok_or_else(|| ConversionError::InvalidInput("Missing probed duration".to_string()))?;
+    let target_duration = trim_duration(&config)
+        .or_else(|| effective_trim_duration(&config.segments, Some(source_duration)))
+        .unwrap_or(source_duration);
+    if target_duration <= 0.0 {
+        return Err(ConversionError::InvalidInput(
+            "Could not determine a target duration".to_string(),
+        ));
+    }
+
+    let target_total_kbps = target_mb * 1024.0 * 1024.0 * 8.0 / 1000.0 / target_duration;
+    let audio_kbps = config.audio_bitrate.parse::<f64>().unwrap_or(0.0);
+    let target_video_kbps = (target_total_kbps - audio_kbps).max(0.0);
+
+    if config.video_bitrate_mode == "bitrate" {
+        let achievable = target_video_kbps >= MIN_ACHIEVABLE_VIDEO_KBPS;
+        let note = if achievable {
+            format!(
+                "{:.0} kbps video bitrate should land near {:.1} MB",
+                target_video_kbps, target_mb
+            )
+        } else {
+            format!(
+                "{:.1} MB leaves only {:.0} kbps for video after audio; below a sane quality floor",
+                target_mb, target_video_kbps
+            )
+        };
+        return Ok(SizeSuggestion {
+            suggested_bitrate_kbps: Some(target_video_kbps),
+            suggested_crf: None,
+            achievable,
+            note,
+        });
+    }
+
+    let (source_width, source_height) = metadata_dimensions(&metadata);
+    let (width, height) = determine_target_dimensions(&config, source_width, source_height);
+    let fps = determine_target_fps(&config, metadata.frame_rate.unwrap_or(30.0));
+    let pixel_rate = width as f64 * height as f64 * fps;
+    if pixel_rate <= 0.0 || target_video_kbps <= 0.0 {
+        return Ok(SizeSuggestion {
+            suggested_bitrate_kbps: None,
+            suggested_crf: None,
+            achievable: false,
+            note: "Could not determine the target resolution or frame rate".to_string(),
+        });
+    }
+
+    let reference = codec_reference(&config.video_codec);
+    let target_bits_per_pixel = target_video_kbps * 1000.0 / pixel_rate;
+    // Inverse of estimate_quality_video_bitrate's `scale = 2^(-delta/6)`.
+    let crf_delta = -6.0 * (target_bits_per_pixel / reference.bits_per_pixel_at_reference).log2();
+    let raw_crf = reference.reference_crf + crf_delta;
+
+    let range = crf_range_for_codec(&config.video_codec);
+    let clamped_crf = raw_crf.clamp(*range.start() as f64, *range.end() as f64);
+    // Higher CRF/quality-scale values mean lower quality; clamping down to
+    // the codec's floor (its worst quality) is the only direction that can
+    // fail to reach a small enough target.
+    let achievable = raw_crf <= *range.end() as f64;
+    let note = if achievable {
+        format!(
+            "CRF/quality ~{:.0} should land near {:.1} MB",
+            clamped_crf, target_mb
+        )
+    } else {
+        format!(
+            "{:.1} MB is smaller than {} can reach even at its lowest quality setting",
+            target_mb, config.video_codec
+        )
+    };
+
+    Ok(SizeSuggestion {
+        suggested_bitrate_kbps: None,
+        suggested_crf: Some(clamped_crf),
+        achievable,
+        note,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_config() -> ConversionConfig {
+        ConversionConfig {
+            container: "mp4".into(),
+            video_codec: "libx264".into(),
+            video_bitrate_mode: "crf".into(),
+            audio_codec: "aac".into(),
+            audio_bitrate: "128".into(),
+            crf: 23,
+            preset: "medium".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_codec_reference_known_codecs() {
+        assert!(codec_reference("libx265").bits_per_pixel_at_reference > 0.0);
+        assert!(codec_reference("h264_qsv").bits_per_pixel_at_reference > 0.0);
+        assert!(codec_reference("h264_amf").bits_per_pixel_at_reference > 0.0);
+    }
+
+    #[test]
+    fn test_effective_crf_for_quality_nvenc_vaapi_amf_share_cq_formula() {
+        assert_eq!(effective_crf_for_quality("h264_nvenc", 50), 27.0);
+        assert_eq!(effective_crf_for_quality("hevc_vaapi", 50), 27.0);
+        assert_eq!(effective_crf_for_quality("h264_amf", 50), 27.0);
+        assert_eq!(effective_crf_for_quality("h264_nvenc", 100), 2.0);
+        assert_eq!(effective_crf_for_quality("h264_nvenc", 0), 51.0);
+    }
+
+    #[test]
+    fn test_effective_crf_for_quality_qsv_passes_through() {
+        assert_eq!(effective_crf_for_quality("h264_qsv", 50), 50.0);
+        assert_eq!(effective_crf_for_quality("av1_qsv", 30), 30.0);
+    }
+
+    #[test]
+    fn test_effective_crf_for_quality_videotoolbox_is_mirrored() {
+        assert_eq!(effective_crf_for_quality("h264_videotoolbox", 50), 50.0);
+        assert_eq!(effective_crf_for_quality("h264_videotoolbox", 80), 20.0);
+    }
+
+    #[test]
+    fn test_estimate_quality_video_bitrate_reads_quality_not_crf_for_hardware_codecs() {
+        let mut config = sample_config();
+        config.video_codec = "h264_nvenc".into();
+        config.crf = 0; // Irrelevant for nvenc; must not affect the estimate.
+        config.quality = 50;
+        let at_default_quality = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+        config.quality = 90;
+        let at_higher_quality = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+        assert!(at_higher_quality > at_default_quality);
+    }
+
+    #[test]
+    fn test_estimate_quality_video_bitrate_hardware_codecs_shrink_h264_to_hevc_to_av1() {
+        for family in ["nvenc", "qsv", "amf", "vaapi"] {
+            let mut config = sample_config();
+            config.quality = 50;
+
+            config.video_codec = format!("h264_{}", family);
+            let h264_estimate = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+            config.video_codec = format!("hevc_{}", family);
+            let hevc_estimate = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+            config.video_codec = format!("av1_{}", family);
+            let av1_estimate = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+            assert!(
+                h264_estimate > hevc_estimate && hevc_estimate > av1_estimate,
+                "{} family did not shrink h264 > hevc > av1: {} / {} / {}",
+                family,
+                h264_estimate,
+                hevc_estimate,
+                av1_estimate
+            );
+        }
+    }
+
+    #[test]
+    fn test_estimate_quality_video_bitrate_videotoolbox_shrinks_h264_to_hevc() {
+        let mut config = sample_config();
+        config.quality = 50;
+
+        config.video_codec = "h264_videotoolbox".into();
+        let h264_estimate = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+        config.video_codec = "hevc_videotoolbox".into();
+        let hevc_estimate = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+        assert!(h264_estimate > hevc_estimate);
+    }
+
+    #[test]
+    fn test_determine_target_dimensions_never_upscales_by_default() {
+        let mut config = sample_config();
+        config.resolution = "1080p".into();
+
+        assert_eq!(determine_target_dimensions(&config, 1280, 720), (1280, 720));
+    }
+
+    #[test]
+    fn test_determine_target_dimensions_still_downscales_from_4k() {
+        let mut config = sample_config();
+        config.resolution = "1080p".into();
+
+        assert_eq!(determine_target_dimensions(&config, 3840, 2160), (1920, 1080));
+    }
+
+    #[test]
+    fn test_determine_target_dimensions_upscale_allowed() {
+        let mut config = sample_config();
+        config.resolution = "1080p".into();
+        config.allow_upscale = true;
+
+        assert_eq!(determine_target_dimensions(&config, 1280, 720), (1920, 1080));
+    }
+
+    #[test]
+    fn test_determine_target_dimensions_1440p_and_2160p() {
+        let mut config = sample_config();
+        config.resolution = "2160p".into();
+        config.allow_upscale = true;
+
+        assert_eq!(determine_target_dimensions(&config, 1920, 1080), (3840, 2160));
+
+        config.resolution = "1440p".into();
+        assert_eq!(determine_target_dimensions(&config, 1920, 1080), (2560, 1440));
+    }
+
+    #[test]
+    fn test_determine_target_dimensions_unknown_preset_keeps_source_size() {
+        let mut config = sample_config();
+        config.resolution = "potato".into();
+
+        assert_eq!(determine_target_dimensions(&config, 1280, 720), (1280, 720));
+    }
+
+    #[test]
+    fn test_determine_target_fps_falls_back_to_source_for_original() {
+        let mut config = sample_config();
+        config.fps = "original".into();
+
+        assert_eq!(determine_target_fps(&config, 24.0), 24.0);
+    }
+
+    #[test]
+    fn test_determine_target_fps_resolves_alias() {
+        let mut config = sample_config();
+        config.fps = "ntsc".into();
+
+        let target = determine_target_fps(&config, 24.0);
+        assert!((target - (30000.0 / 1001.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimate_output_video_bitrate_lossless_uses_source_bitrate() {
+        let mut config = sample_config();
+        config.lossless = true;
+
+        let estimate = estimate_output_video_bitrate(&config, 1920, 1080, 30.0, 8000.0);
+
+        assert_eq!(estimate, 8000.0);
+    }
+
+    #[test]
+    fn test_estimate_output_video_bitrate_non_lossless_uses_curve() {
+        let config = sample_config();
+
+        let estimate = estimate_output_video_bitrate(&config, 1920, 1080, 30.0, 8000.0);
+        let curve_estimate = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+        assert_eq!(estimate, curve_estimate);
+    }
+
+    #[test]
+    fn test_estimate_output_video_bitrate_stream_copy_uses_source_bitrate_verbatim() {
+        let mut config = sample_config();
+        config.video_codec = "copy".into();
+
+        // A downscale to 720p and a halved fps would both change the CRF
+        // curve's answer; stream copy must ignore them entirely.
+        let estimate = estimate_output_video_bitrate(&config, 1280, 720, 15.0, 8000.0);
+
+        assert_eq!(estimate, 8000.0);
+    }
+
+    #[test]
+    fn test_estimate_output_video_bitrate_stream_copy_with_unknown_source_bitrate() {
+        let mut config = sample_config();
+        config.video_codec = "copy".into();
+
+        let estimate = estimate_output_video_bitrate(&config, 1920, 1080, 30.0, 0.0);
+
+        assert_eq!(estimate, 0.0);
+    }
+
+    #[test]
+    fn test_estimate_quality_video_bitrate_scales_with_crf() {
+        let mut config = sample_config();
+        config.video_codec = "libx264".into();
+        config.crf = 23;
+        let baseline = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+        config.crf = 17;
+        let higher_quality = estimate_quality_video_bitrate(&config, 1920, 1080, 30.0);
+
+        assert!(higher_quality > baseline);
+    }
+
+    #[test]
+    fn test_estimate_output_size_bytes_scales_with_duration() {
+        let config = sample_config();
+
+        let short = estimate_output_size_bytes(&config, 1920, 1080, 30.0, 8000.0, 10.0);
+        let long = estimate_output_size_bytes(&config, 1920, 1080, 30.0, 8000.0, 20.0);
+
+        assert!(long > short);
+        assert_eq!(long, short * 2);
+    }
+
+    #[test]
+    fn test_estimate_output_size_bytes_includes_audio_bitrate() {
+        let mut config = sample_config();
+        config.audio_bitrate = "0".into();
+        let no_audio = estimate_output_size_bytes(&config, 1920, 1080, 30.0, 8000.0, 10.0);
+
+        config.audio_bitrate = "320".into();
+        let with_audio = estimate_output_size_bytes(&config, 1920, 1080, 30.0, 8000.0, 10.0);
+
+        assert!(with_audio > no_audio);
+    }
+
+    fn sample_metadata() -> ProbeMetadata {
+        ProbeMetadata {
+            duration: Some("60.0".to_string()),
+            width: Some(1920),
+            height: Some(1080),
+            frame_rate: Some(30.0),
+            video_bitrate_kbps: Some(8000.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_estimate_output_bitrate_mode_has_near_zero_spread() {
+        let mut config = sample_config();
+        config.video_bitrate_mode = "bitrate".into();
+
+        let estimate = estimate_output_calibrated(&config, &sample_metadata(), None).unwrap();
+
+        assert_eq!(estimate.confidence, "high");
+        let spread = (estimate.size_mb_high - estimate.size_mb_low) / estimate.size_mb;
+        assert!(spread < 0.05);
+    }
+
+    #[test]
+    fn test_estimate_output_crf_without_source_bitrate_has_widest_spread() {
+        let config = sample_config();
+        let mut metadata = sample_metadata();
+        metadata.video_bitrate_kbps = None;
+
+        let estimate = estimate_output_calibrated(&config, &sample_metadata(), None).unwrap();
+        let estimate_no_bitrate = estimate_output_calibrated(&config, &metadata, None).unwrap();
+
+        let spread = |e: &OutputEstimate| (e.size_mb_high - e.size_mb_low) / e.size_mb;
+        assert!(spread(&estimate_no_bitrate) > spread(&estimate));
+        assert_eq!(estimate_no_bitrate.confidence, "low");
+    }
+
+    #[test]
+    fn test_estimate_output_midpoint_matches_size_bytes_estimate() {
+        let config = sample_config();
+        let metadata = sample_metadata();
+
+        let estimate = estimate_output_calibrated(&config, &metadata, None).unwrap();
+        let size_bytes = estimate_output_size_bytes(&config, 1920, 1080, 30.0, 8000.0, 60.0);
+
+        assert!((estimate.size_mb - size_bytes as f64 / (1024.0 * 1024.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimate_output_requires_duration() {
+        let config = sample_config();
+        let mut metadata = sample_metadata();
+        metadata.duration = None;
+
+        assert!(estimate_output_calibrated(&config, &metadata, None).is_err());
+    }
+
+    #[test]
+    fn test_suggest_settings_for_size_bitrate_mode_round_trips() {
+        let mut config = sample_config();
+        config.video_bitrate_mode = "bitrate".into();
+        let metadata = sample_metadata();
+        let target_mb = 50.0;
+
+        let suggestion =
+            suggest_settings_for_size(metadata.clone(), config.clone(), target_mb).unwrap();
+        assert!(suggestion.achievable);
+        config.video_bitrate = suggestion.suggested_bitrate_kbps.unwrap().to_string();
+
+        let estimate = estimate_output_calibrated(&config, &metadata, None).unwrap();
+        assert!((estimate.size_mb - target_mb).abs() / target_mb < 0.05);
+    }
+
+    #[test]
+    fn test_suggest_settings_for_size_crf_mode_round_trips() {
+        let config = sample_config();
+        let metadata = sample_metadata();
+        let target_mb = 20.0;
+
+        let suggestion =
+            suggest_settings_for_size(metadata.clone(), config.clone(), target_mb).unwrap();
+        assert!(suggestion.achievable);
+
+        let mut retried_config = config;
+        retried_config.crf = suggestion.suggested_crf.unwrap().round() as u32;
+        let estimate = estimate_output_calibrated(&retried_config, &metadata, None).unwrap();
+        assert!((estimate.size_mb - target_mb).abs() / target_mb < 0.05);
+    }
+
+    #[test]
+    fn test_suggest_settings_for_size_flags_unachievable_tiny_target() {
+        let mut config = sample_config();
+        config.video_bitrate_mode = "bitrate".into();
+        let metadata = sample_metadata();
+
+        let suggestion = suggest_settings_for_size(metadata, config, 0.01).unwrap();
+        assert!(!suggestion.achievable);
+    }
+
+    #[test]
+    fn test_suggest_settings_for_size_requires_duration() {
+        let config = sample_config();
+        let mut metadata = sample_metadata();
+        metadata.duration = None;
+
+        assert!(suggest_settings_for_size(metadata, config, 50.0).is_err());
+    }
+
+    #[test]
+    fn test_estimate_output_swaps_dimensions_for_portrait_rotation() {
+        // A phone-shot portrait clip reported with landscape dimensions
+        // (1920x1080) plus a 90 degree rotation; the estimate should scale
+        // against the 1080x1920 it actually displays as, not the raw decode.
+        let mut config = sample_config();
+        config.resolution = "1080p".into();
+        let mut portrait_metadata = sample_metadata();
+        portrait_metadata.rotation_degrees = Some(90);
+        let mut landscape_metadata = sample_metadata();
+        landscape_metadata.width = Some(1080);
+        landscape_metadata.height = Some(1920);
+
+        let portrait_estimate =
+            estimate_output_calibrated(&config, &portrait_metadata, None).unwrap();
+        let landscape_estimate =
+            estimate_output_calibrated(&config, &landscape_metadata, None).unwrap();
+
+        assert!((portrait_estimate.size_mb - landscape_estimate.size_mb).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_suggest_settings_for_size_accounts_for_portrait_rotation() {
+        let mut config = sample_config();
+        let mut metadata = sample_metadata();
+        metadata.rotation_degrees = Some(270);
+
+        let rotated = suggest_settings_for_size(metadata, config.clone(), 50.0).unwrap();
+
+        config.resolution = "original".into();
+        let mut unrotated_swapped = sample_metadata();
+        unrotated_swapped.width = Some(1080);
+        unrotated_swapped.height = Some(1920);
+        let reference = suggest_settings_for_size(unrotated_swapped, config, 50.0).unwrap();
+
+        assert_eq!(rotated.suggested_crf, reference.suggested_crf);
+    }
+}
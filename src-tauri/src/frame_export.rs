@@ -0,0 +1,202 @@
+//! Exports individual full-resolution frames at specific timestamps to a
+//! user-chosen directory, one ffmpeg invocation per timestamp with a
+//! fast-seek `-ss` before `-i` so long files don't pay for a full decode
+//! just to grab a handful of frames.
+
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+
+use crate::conversion::{ConversionError, probe_media, validate_output_directory};
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FrameExportResult {
+    pub timestamp_secs: f64,
+    pub path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Formats seconds as `HH-MM-SS.mmm` for use in a filename; colons aren't
+/// safe in filenames on every filesystem, so this differs from the
+/// `HH:MM:SS.mmm` format ffmpeg itself expects for `-ss`.
+fn format_timestamp_for_filename(seconds: f64) -> String {
+    let total_millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    let hours = total_millis / 3_600_000;
+    let minutes = (total_millis / 60_000) % 60;
+    let secs = (total_millis / 1_000) % 60;
+    let millis = total_millis % 1_000;
+    format!("{:02}-{:02}-{:02}.{:03}", hours, minutes, secs, millis)
+}
+
+/// Maps a 0..=100 quality knob to the ffmpeg flags each format actually
+/// understands: JPEG's `-q:v` runs backwards (2 is best, 31 is worst), and
+/// PNG is lossless so quality instead controls how hard to compress.
+fn quality_args(format: &str, quality: u32) -> Vec<String> {
+    let clamped = quality.min(100);
+    match format {
+        "png" => {
+            let level = 9 - (clamped * 9 / 100);
+            vec!["-compression_level".to_string(), level.to_string()]
+        }
+        _ => {
+            let qscale = 2 + ((100 - clamped) * 29 / 100);
+            vec!["-q:v".to_string(), qscale.to_string()]
+        }
+    }
+}
+
+/// Grabs a single frame at `timestamp_secs` via fast seek and writes it to
+/// `output_path`.
+async fn extract_single_frame(
+    app: &AppHandle,
+    file_path: &str,
+    timestamp_secs: f64,
+    format: &str,
+    quality: u32,
+    output_path: &Path,
+) -> Result<(), ConversionError> {
+    let mut args = vec![
+        "-ss".to_string(),
+        timestamp_secs.to_string(),
+        "-i".to_string(),
+        file_path.to_string(),
+        "-frames:v".to_string(),
+        "1".to_string(),
+    ];
+    args.extend(quality_args(format, quality));
+    args.push("-y".to_string());
+    args.push(output_path.to_string_lossy().to_string());
+
+    let cmd = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .args(args);
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+
+    if output.status.success() && output_path.is_file() {
+        Ok(())
+    } else {
+        Err(ConversionError::Shell(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// Exports one image per entry in `timestamps` from `file_path` into
+/// `output_dir` (validated to exist and be writable up front), named
+/// `<stem>_HH-MM-SS.mmm.<format>`. A timestamp past the probed duration, or
+/// one ffmpeg otherwise fails to extract, is reported as a failed item in
+/// the returned vector rather than aborting the whole call.
+#[tauri::command]
+pub(crate) async fn extract_frames(
+    app: AppHandle,
+    file_path: String,
+    timestamps: Vec<f64>,
+    format: String,
+    quality: u32,
+    output_dir: String,
+) -> Result<Vec<FrameExportResult>, ConversionError> {
+    let dir = Path::new(&output_dir);
+    validate_output_directory(dir)?;
+
+    let duration_secs = probe_media(app.clone(), file_path.clone())
+        .await?
+        .duration
+        .as_deref()
+        .and_then(|d| d.parse::<f64>().ok());
+
+    let stem = Path::new(&file_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut results = Vec::with_capacity(timestamps.len());
+    for timestamp_secs in timestamps {
+        if duration_secs.is_some_and(|duration| timestamp_secs > duration) {
+            results.push(FrameExportResult {
+                timestamp_secs,
+                path: None,
+                error: Some("Timestamp is beyond the probed duration".to_string()),
+            });
+            continue;
+        }
+
+        let filename = format!(
+            "{}_{}.{}",
+            stem,
+            format_timestamp_for_filename(timestamp_secs),
+            format
+        );
+        let output_path = dir.join(filename);
+
+        match extract_single_frame(
+            &app,
+            &file_path,
+            timestamp_secs,
+            &format,
+            quality,
+            &output_path,
+        )
+        .await
+        {
+            Ok(()) => results.push(FrameExportResult {
+                timestamp_secs,
+                path: Some(output_path.to_string_lossy().to_string()),
+                error: None,
+            }),
+            Err(e) => results.push(FrameExportResult {
+                timestamp_secs,
+                path: None,
+                error: Some(e.to_string()),
+            }),
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp_for_filename_uses_dashes() {
+        assert_eq!(format_timestamp_for_filename(83.45), "00-01-23.450");
+    }
+
+    #[test]
+    fn test_format_timestamp_for_filename_clamps_negative_to_zero() {
+        assert_eq!(format_timestamp_for_filename(-1.0), "00-00-00.000");
+    }
+
+    #[test]
+    fn test_quality_args_jpeg_runs_backwards() {
+        assert_eq!(
+            quality_args("jpg", 100),
+            vec!["-q:v".to_string(), "2".to_string()]
+        );
+        assert_eq!(
+            quality_args("jpg", 0),
+            vec!["-q:v".to_string(), "31".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_quality_args_png_uses_compression_level() {
+        assert_eq!(
+            quality_args("png", 100),
+            vec!["-compression_level".to_string(), "0".to_string()]
+        );
+        assert_eq!(
+            quality_args("png", 0),
+            vec!["-compression_level".to_string(), "9".to_string()]
+        );
+    }
+}
@@ -1,38 +1,86 @@
+mod cli;
 mod conversion;
+mod diagnostics;
+mod estimation;
+mod frame_export;
+mod media;
+mod orphan_cleanup;
+mod pending_open_files;
+mod presets;
+mod preview;
+mod quality;
+mod reveal;
+mod sample_estimate;
+mod single_instance;
+mod system_info;
+mod taskbar_progress;
+mod thumbnails;
+mod waveform;
+mod window_state;
+use std::time::Duration;
+
 use tauri::window::{Color, EffectState};
 use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
 use tauri_plugin_store::Builder as StoreBuilder;
 
+/// How long the splash is allowed to stay up before the watchdog in `setup`
+/// force-closes it and shows the main window itself, in case the frontend
+/// never gets far enough to invoke `close_splash` (e.g. it crashed on load).
+const SPLASH_WATCHDOG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Retries `show()` a few times with a short delay between attempts, since a
+/// window that's still mid-setup (e.g. right after a vibrancy effect was
+/// applied) can transiently fail to show on some platforms.
+async fn show_main_window(app: &tauri::AppHandle) -> Result<(), String> {
+    let main = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+
+    let mut last_error = String::new();
+    for attempt in 0..3 {
+        match main.show() {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                last_error = e.to_string();
+                if attempt < 2 {
+                    tokio::time::sleep(Duration::from_millis(150)).await;
+                }
+            }
+        }
+    }
+    Err(format!("Failed to show main window: {}", last_error))
+}
+
 #[tauri::command]
-async fn close_splash(window: tauri::Window) {
+async fn close_splash(window: tauri::Window) -> Result<(), String> {
     if let Some(splash) = window.get_webview_window("splash") {
-        splash.close().unwrap();
+        let _ = splash.close();
     }
-    window.get_webview_window("main").unwrap().show().unwrap();
+    show_main_window(window.app_handle()).await
 }
 
 #[cfg(target_os = "macos")]
 fn apply_window_effect(window: &tauri::WebviewWindow) {
     use tauri::window::{Effect, EffectsBuilder};
 
-    window
-        .set_effects(
-            EffectsBuilder::new()
-                .effect(Effect::HudWindow)
-                .state(EffectState::Active)
-                .radius(16.0)
-                .build(),
-        )
-        .expect("Unsupported platform! 'HudWindow' effect is only supported on macOS");
+    if let Err(e) = window.set_effects(
+        EffectsBuilder::new()
+            .effect(Effect::HudWindow)
+            .state(EffectState::Active)
+            .radius(16.0)
+            .build(),
+    ) {
+        eprintln!("Failed to apply HudWindow vibrancy effect: {}", e);
+    }
 }
 
 #[cfg(target_os = "windows")]
 fn apply_window_effect(window: &tauri::WebviewWindow) {
     use tauri::window::{Effect, EffectsBuilder};
 
-    window
-        .set_effects(EffectsBuilder::new().effect(Effect::Acrylic).build())
-        .expect("Unsupported platform! 'Acrylic' effect is only supported on Windows");
+    if let Err(e) = window.set_effects(EffectsBuilder::new().effect(Effect::Acrylic).build()) {
+        eprintln!("Failed to apply Acrylic mica effect: {}", e);
+    }
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
@@ -40,11 +88,27 @@ fn apply_window_effect(_window: &tauri::WebviewWindow) {}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let argv: Vec<String> = std::env::args().collect();
+    if let Some(cli_args) = cli::parse_args(&argv) {
+        std::process::exit(cli::run_headless(cli_args));
+    }
+
     tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            single_instance::handle_forwarded_launch(app, argv);
+        }))
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .setup(|app| {
+            // Collect any files passed on the command line (e.g. "Open With
+            // Frame") before either window exists to receive a `files-opened`
+            // event.
+            app.manage(pending_open_files::PendingOpenFiles::default());
+            let launch_args: Vec<String> = std::env::args().skip(1).collect();
+            app.state::<pending_open_files::PendingOpenFiles>()
+                .extend(pending_open_files::validate_paths(launch_args));
+
             let builder =
                 WebviewWindowBuilder::new(app, "main", WebviewUrl::App("index.html".into()))
                     .title("Frame")
@@ -61,21 +125,52 @@ pub fn run() {
 
             apply_window_effect(&window);
 
-            let splash = WebviewWindowBuilder::new(app, "splash", WebviewUrl::App("splash".into()))
-                .title("Splash")
-                .inner_size(300.0, 300.0)
-                .resizable(false)
-                .decorations(false)
-                .always_on_top(true)
-                .transparent(true)
-                .background_color(Color(0, 0, 0, 0))
-                .visible(false)
-                .build()
-                .unwrap();
+            let restored_state = window_state::restore_and_track(&app.handle().clone(), &window);
+
+            let mut splash_builder =
+                WebviewWindowBuilder::new(app, "splash", WebviewUrl::App("splash".into()))
+                    .title("Splash")
+                    .inner_size(300.0, 300.0)
+                    .resizable(false)
+                    .decorations(false)
+                    .always_on_top(true)
+                    .transparent(true)
+                    .background_color(Color(0, 0, 0, 0))
+                    .visible(false);
+
+            if let Some(main_state) = &restored_state {
+                let pos = window_state::centered_splash_position(main_state, 300.0, 300.0);
+                splash_builder = splash_builder.position(pos.x as f64, pos.y as f64);
+            }
+
+            let splash = splash_builder.build().unwrap();
 
             apply_window_effect(&splash);
 
+            // Backstop for `close_splash`: if the frontend never gets far
+            // enough to invoke it (a crash on load, a stalled asset fetch),
+            // the user shouldn't be stuck staring at the splash forever.
+            let watchdog_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(SPLASH_WATCHDOG_TIMEOUT).await;
+                if let Some(splash) = watchdog_handle.get_webview_window("splash") {
+                    let _ = splash.close();
+                }
+                if let Err(e) = show_main_window(&watchdog_handle).await {
+                    eprintln!("Splash watchdog failed to show main window: {}", e);
+                }
+            });
+
+            app.manage(conversion::EncoderCache::default());
+            app.manage(conversion::HardwareEncoderCache::default());
             app.manage(conversion::ConversionManager::new(app.handle().clone()));
+            app.manage(conversion::SidecarStatusCache::default());
+            app.manage(thumbnails::FilmstripJobs::default());
+            app.manage(waveform::AudioPeaksCache::default());
+            app.manage(preview::PreviewJobs::default());
+            app.manage(sample_estimate::SampleEstimateJobs::default());
+            preview::clear_preview_cache(&app.handle().clone());
+            taskbar_progress::init(&app.handle().clone());
 
             Ok(())
         })
@@ -88,14 +183,118 @@ pub fn run() {
         .plugin(StoreBuilder::new().build())
         .invoke_handler(tauri::generate_handler![
             conversion::queue_conversion,
+            conversion::queue_conversions_batch,
+            conversion::queue_directory,
+            conversion::extract_audio,
+            conversion::queue_concat,
+            conversion::queue_remux,
             conversion::pause_conversion,
             conversion::resume_conversion,
             conversion::cancel_conversion,
+            conversion::stop_conversion,
+            conversion::get_queue_state,
+            conversion::get_queue_progress,
+            conversion::reorder_queue,
+            conversion::move_to_front,
+            conversion::set_task_priority,
+            conversion::clear_restored_queue,
+            conversion::get_failed_tasks,
+            conversion::retry_conversion,
+            conversion::pause_all_conversions,
+            conversion::resume_all_conversions,
+            conversion::cancel_all_conversions,
             conversion::probe_media,
+            conversion::check_sidecars,
+            conversion::list_encoders,
+            conversion::detect_hardware_encoders,
             conversion::get_max_concurrency,
             conversion::set_max_concurrency,
+            conversion::get_recommended_concurrency,
+            conversion::get_compatibility,
+            system_info::get_system_info,
+            conversion::get_default_threads,
+            conversion::set_default_threads,
+            conversion::get_background_priority,
+            conversion::set_background_priority,
+            conversion::get_keep_partial_on_error,
+            conversion::set_keep_partial_on_error,
+            conversion::get_disk_space_check,
+            conversion::set_disk_space_check,
+            conversion::get_fill_paused_slots,
+            conversion::set_fill_paused_slots,
+            conversion::get_on_queue_complete_action,
+            conversion::set_on_queue_complete_action,
+            conversion::get_skip_power_action_if_all_failed,
+            conversion::set_skip_power_action_if_all_failed,
+            conversion::cancel_queue_complete_action,
+            conversion::get_notification_preferences,
+            conversion::set_notification_preferences,
+            conversion::get_stall_watchdog_settings,
+            conversion::set_stall_watchdog_settings,
+            conversion::get_output_settings,
+            conversion::set_output_settings,
+            conversion::get_mirror_logs_to_disk,
+            conversion::set_mirror_logs_to_disk,
+            conversion::get_include_failed_outputs_in_orphan_scan,
+            conversion::set_include_failed_outputs_in_orphan_scan,
+            conversion::get_task_log,
+            conversion::get_event_throttle_settings,
+            conversion::set_event_throttle_settings,
+            conversion::get_conversion_history,
+            conversion::clear_conversion_history,
+            conversion::delete_history_entry,
+            conversion::get_estimation_calibration,
+            conversion::reset_estimation_calibration,
+            thumbnails::generate_thumbnail,
+            thumbnails::generate_filmstrip,
+            thumbnails::cancel_filmstrip,
+            waveform::get_audio_peaks,
+            preview::generate_preview,
+            preview::cancel_preview,
+            sample_estimate::estimate_output_accurate,
+            sample_estimate::cancel_estimate_output_accurate,
+            estimation::estimate_output,
+            estimation::suggest_settings_for_size,
+            quality::compare_quality,
+            frame_export::extract_frames,
+            presets::save_preset,
+            presets::list_presets,
+            presets::delete_preset,
+            presets::rename_preset,
+            presets::export_presets,
+            presets::import_presets,
+            reveal::reveal_in_file_manager,
+            orphan_cleanup::list_orphaned_artifacts,
+            orphan_cleanup::clean_orphaned_artifacts,
+            diagnostics::export_diagnostics,
+            pending_open_files::take_pending_open_files,
             close_splash
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| match event {
+            // macOS Finder opening a file (or dropping one on the dock icon);
+            // the single-instance plugin only covers argv forwarding on
+            // relaunch, not this path. Can fire before the splash has closed,
+            // so it goes through the same dispatch-or-stash routing.
+            tauri::RunEvent::Opened { urls } => {
+                let paths = urls
+                    .into_iter()
+                    .filter_map(|url| url.to_file_path().ok())
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect();
+                pending_open_files::dispatch(app, pending_open_files::validate_paths(paths));
+            }
+            // macOS dock icon clicked while running with no visible windows.
+            tauri::RunEvent::Reopen {
+                has_visible_windows: false,
+                ..
+            } => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+            _ => {}
+        });
 }
@@ -0,0 +1,329 @@
+//! Probe result types and the small parsing helpers that build them, shared
+//! between `conversion::probe_media` (which populates a [`ProbeMetadata`]
+//! from ffprobe's JSON) and `estimation` (which reads one back to size up an
+//! encode). Kept in one place so a field added here exists for both sides
+//! rather than drifting into two almost-identical copies.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioTrack {
+    pub index: u32,
+    pub codec: String,
+    pub channels: String,
+    pub language: Option<String>,
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bitrate_kbps: Option<f64>,
+    pub sample_rate: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtitleTrack {
+    pub index: u32,
+    pub codec: String,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub forced: bool,
+    pub default: bool,
+    /// True for bitmap subtitle formats (PGS, DVD/VOBSUB, DVB), which need to
+    /// be burned in or passed through as-is rather than transcoded to another
+    /// text-based subtitle codec.
+    pub image_based: bool,
+}
+
+/// Subtitle codec names ffprobe reports for bitmap (image-based) subtitle
+/// formats, as opposed to text-based ones like SRT/ASS/MOV_TEXT.
+const IMAGE_BASED_SUBTITLE_CODECS: &[&str] = &[
+    "hdmv_pgs_subtitle",
+    "pgssub",
+    "dvd_subtitle",
+    "dvdsub",
+    "dvb_subtitle",
+    "xsub",
+];
+
+pub(crate) fn is_image_based_subtitle_codec(codec: &str) -> bool {
+    IMAGE_BASED_SUBTITLE_CODECS.contains(&codec)
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct FfprobeTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub date: Option<String>,
+    #[serde(rename = "creation_time")]
+    pub creation_time: Option<String>,
+    pub timecode: Option<String>,
+    pub language: Option<String>,
+    pub comment: Option<String>,
+    #[serde(rename = "DESCRIPTION")]
+    pub description_upper: Option<String>,
+    #[serde(rename = "DATE")]
+    pub date_upper: Option<String>,
+    pub rotate: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeMetadata {
+    pub duration: Option<String>,
+    pub bitrate: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub resolution: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_bitrate_kbps: Option<f64>,
+    pub audio_tracks: Vec<AudioTrack>,
+    #[serde(default)]
+    pub subtitle_tracks: Vec<SubtitleTrack>,
+    #[serde(default)]
+    pub tags: Option<FfprobeTags>,
+    pub pixel_format: Option<String>,
+    pub color_space: Option<String>,
+    pub color_range: Option<String>,
+    pub color_primaries: Option<String>,
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+    #[serde(default)]
+    pub attachment_count: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_count: Option<u32>,
+    /// Clockwise rotation (0/90/180/270) the source's `rotate` tag or
+    /// display-matrix side data says the decoded frame needs to display
+    /// upright, e.g. a phone-shot portrait clip muxed as a landscape frame.
+    /// `width`/`height` above are always the raw decoded frame size; use
+    /// [`metadata_dimensions`] to get the orientation the video actually
+    /// displays in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation_degrees: Option<i32>,
+}
+
+/// The source's actual display dimensions: `width`/`height` as ffprobe
+/// reports them for the raw decoded frame, swapped when `rotation_degrees`
+/// is 90 or 270 so portrait phone footage (reported landscape, with a
+/// rotation tag/side-data to correct it) scales and estimates against the
+/// orientation it will actually display in.
+pub(crate) fn metadata_dimensions(metadata: &ProbeMetadata) -> (u32, u32) {
+    let width = metadata.width.unwrap_or(0);
+    let height = metadata.height.unwrap_or(0);
+    match metadata.rotation_degrees {
+        Some(90) | Some(270) => (height, width),
+        _ => (width, height),
+    }
+}
+
+/// Parses an fps value that may be a plain decimal ("23.976"), an exact
+/// "num/den" fraction (the form ffprobe's `avg_frame_rate` reports, e.g.
+/// "24000/1001"), or one of the "ntsc"/"pal"/"film" aliases a user-facing fps
+/// setting can use. Returns `None` for an empty string or ffprobe's "N/A"
+/// sentinel rather than erroring, since a frame rate is never essential to
+/// have.
+pub(crate) fn parse_frame_rate_string(value: &str) -> Option<f64> {
+    let resolved = match value.trim() {
+        "" => return None,
+        v if v.eq_ignore_ascii_case("n/a") => return None,
+        "ntsc" => "30000/1001",
+        "pal" => "25/1",
+        "film" => "24000/1001",
+        other => other,
+    };
+
+    if let Some((num, den)) = resolved.split_once('/') {
+        let numerator: f64 = num.trim().parse().ok()?;
+        let denominator: f64 = den.trim().parse().ok()?;
+        if denominator == 0.0 {
+            return None;
+        }
+        Some(numerator / denominator)
+    } else {
+        resolved.trim().parse().ok()
+    }
+}
+
+/// Parses an ffprobe bitrate field (reported in bits/sec, as a string) into
+/// kbps, treating "N/A", empty, and non-positive values as unknown.
+pub(crate) fn parse_probe_bitrate(raw: Option<&str>) -> Option<f64> {
+    let raw = raw?.trim();
+    if raw.eq_ignore_ascii_case("n/a") || raw.is_empty() {
+        return None;
+    }
+    let numeric = raw.parse::<f64>().ok()?;
+    if numeric <= 0.0 {
+        return None;
+    }
+    Some(numeric / 1000.0)
+}
+
+pub(crate) fn is_audio_only_container(container: &str) -> bool {
+    matches!(
+        container.to_lowercase().as_str(),
+        "mp3" | "wav" | "flac" | "aac" | "m4a"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frame_rate_string_decimal_and_fraction() {
+        assert!((parse_frame_rate_string("29.97").unwrap() - 29.97).abs() < 1e-9);
+        assert!((parse_frame_rate_string("30000/1001").unwrap() - 29.97002997).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_frame_rate_string_named_aliases() {
+        let ntsc = parse_frame_rate_string("ntsc").unwrap();
+        let fraction = parse_frame_rate_string("30000/1001").unwrap();
+        assert!((ntsc - fraction).abs() < 1e-9);
+
+        assert!((parse_frame_rate_string("pal").unwrap() - 25.0).abs() < 1e-9);
+        assert!((parse_frame_rate_string("film").unwrap() - 23.976023976).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_frame_rate_string_rejects_na_and_empty() {
+        assert_eq!(parse_frame_rate_string("N/A"), None);
+        assert_eq!(parse_frame_rate_string(""), None);
+    }
+
+    #[test]
+    fn test_parse_probe_bitrate_converts_bits_to_kbps() {
+        assert_eq!(parse_probe_bitrate(Some("128000")), Some(128.0));
+    }
+
+    #[test]
+    fn test_parse_probe_bitrate_rejects_na_empty_and_non_positive() {
+        assert_eq!(parse_probe_bitrate(Some("N/A")), None);
+        assert_eq!(parse_probe_bitrate(Some("")), None);
+        assert_eq!(parse_probe_bitrate(Some("0")), None);
+        assert_eq!(parse_probe_bitrate(None), None);
+    }
+
+    #[test]
+    fn test_is_audio_only_container_matches_known_containers() {
+        assert!(is_audio_only_container("mp3"));
+        assert!(is_audio_only_container("FLAC"));
+        assert!(!is_audio_only_container("mp4"));
+    }
+
+    #[test]
+    fn test_is_image_based_subtitle_codec_distinguishes_bitmap_from_text() {
+        assert!(is_image_based_subtitle_codec("hdmv_pgs_subtitle"));
+        assert!(is_image_based_subtitle_codec("dvd_subtitle"));
+        assert!(!is_image_based_subtitle_codec("subrip"));
+        assert!(!is_image_based_subtitle_codec("ass"));
+    }
+
+    /// `probe_media` sends a `ProbeMetadata` to the frontend as JSON; this
+    /// round-trips a fully populated one through that same representation
+    /// and checks a cross-section of its fields (dimensions, rotation, the
+    /// track/chapter lists and the newer optional counts) came back intact.
+    #[test]
+    fn test_probe_metadata_json_round_trip() {
+        let metadata = ProbeMetadata {
+            duration: Some("125.5".to_string()),
+            bitrate: Some("8000000".to_string()),
+            video_codec: Some("h264".to_string()),
+            audio_codec: Some("aac".to_string()),
+            resolution: Some("1920x1080".to_string()),
+            frame_rate: Some(29.97),
+            width: Some(1920),
+            height: Some(1080),
+            video_bitrate_kbps: Some(7800.0),
+            audio_tracks: vec![AudioTrack {
+                index: 1,
+                codec: "aac".to_string(),
+                channels: "2".to_string(),
+                language: Some("eng".to_string()),
+                label: None,
+                bitrate_kbps: Some(192.0),
+                sample_rate: Some("48000".to_string()),
+            }],
+            tags: Some(FfprobeTags {
+                title: Some("Sample".to_string()),
+                ..Default::default()
+            }),
+            pixel_format: Some("yuv420p".to_string()),
+            color_space: Some("bt709".to_string()),
+            color_range: Some("tv".to_string()),
+            color_primaries: Some("bt709".to_string()),
+            profile: Some("High".to_string()),
+            chapters: vec![Chapter {
+                start: 0.0,
+                end: 60.0,
+                title: Some("Intro".to_string()),
+            }],
+            subtitle_tracks: vec![SubtitleTrack {
+                index: 2,
+                codec: "subrip".to_string(),
+                language: Some("eng".to_string()),
+                title: None,
+                forced: false,
+                default: true,
+                image_based: false,
+            }],
+            attachment_count: 1,
+            frame_count: Some(3000),
+            rotation_degrees: Some(90),
+        };
+
+        let json = serde_json::to_string(&metadata).unwrap();
+        let round_tripped: ProbeMetadata = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(round_tripped.duration, metadata.duration);
+        assert_eq!(round_tripped.width, metadata.width);
+        assert_eq!(round_tripped.height, metadata.height);
+        assert_eq!(round_tripped.audio_tracks.len(), 1);
+        assert_eq!(
+            round_tripped.tags.as_ref().and_then(|t| t.title.clone()),
+            Some("Sample".to_string())
+        );
+        assert_eq!(round_tripped.chapters.len(), 1);
+        assert_eq!(round_tripped.subtitle_tracks.len(), 1);
+        assert_eq!(round_tripped.attachment_count, 1);
+        assert_eq!(round_tripped.frame_count, Some(3000));
+        assert_eq!(round_tripped.rotation_degrees, Some(90));
+    }
+
+    #[test]
+    fn test_metadata_dimensions_swaps_width_and_height_for_90_and_270() {
+        let mut metadata = ProbeMetadata {
+            width: Some(1920),
+            height: Some(1080),
+            ..Default::default()
+        };
+
+        metadata.rotation_degrees = Some(90);
+        assert_eq!(metadata_dimensions(&metadata), (1080, 1920));
+
+        metadata.rotation_degrees = Some(270);
+        assert_eq!(metadata_dimensions(&metadata), (1080, 1920));
+
+        metadata.rotation_degrees = Some(180);
+        assert_eq!(metadata_dimensions(&metadata), (1920, 1080));
+
+        metadata.rotation_degrees = None;
+        assert_eq!(metadata_dimensions(&metadata), (1920, 1080));
+    }
+}
@@ -0,0 +1,210 @@
+//! Finds and removes on-disk leftovers Frame created but never cleaned up —
+//! stray two-pass logs, temp files from a crashed quality comparison or
+//! concat run, and (opt-in) failed conversions' recorded output paths.
+//! Deletion only ever targets paths matching Frame's own naming conventions
+//! or paths Frame itself recorded in history — never arbitrary
+//! pattern-matched user files.
+//!
+//! In-progress tasks at the time of a crash are already handled separately:
+//! `delete_restored_partial_output` cleans those up unconditionally as part
+//! of restoring the persisted queue on startup.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::conversion::{ConversionError, ConversionManager};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OrphanedArtifact {
+    pub path: String,
+    pub kind: String,
+    pub size_bytes: u64,
+}
+
+fn as_orphan(path: PathBuf, kind: &'static str) -> Option<OrphanedArtifact> {
+    let metadata = std::fs::metadata(&path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    Some(OrphanedArtifact {
+        path: path.to_string_lossy().to_string(),
+        kind: kind.to_string(),
+        size_bytes: metadata.len(),
+    })
+}
+
+/// Stray temp files left behind by a crashed quality comparison
+/// (`frame_quality_*.log`, see [`crate::quality::quality_stats_path`]) or
+/// concat run (`frame_concat_*.txt`, see `queue_concat`) that never reached
+/// their own cleanup because the process was killed mid-run.
+fn scan_dir_for_temp_artifacts(dir: &Path) -> Vec<OrphanedArtifact> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let kind = if name.starts_with("frame_quality_") && name.ends_with(".log") {
+                "quality-temp-file"
+            } else if name.starts_with("frame_concat_") && name.ends_with(".txt") {
+                "concat-temp-file"
+            } else {
+                return None;
+            };
+            as_orphan(entry.path(), kind)
+        })
+        .collect()
+}
+
+fn scan_temp_dir() -> Vec<OrphanedArtifact> {
+    scan_dir_for_temp_artifacts(&std::env::temp_dir())
+}
+
+/// Stray ffmpeg two-pass logs (`ffmpeg2pass-*`) sitting in `dir`.
+fn scan_dir_for_two_pass_logs(dir: &Path) -> Vec<OrphanedArtifact> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with("ffmpeg2pass-") {
+                return None;
+            }
+            as_orphan(entry.path(), "two-pass-log")
+        })
+        .collect()
+}
+
+/// Only scanned when an explicit output directory is configured: without
+/// one, outputs land next to whatever source file they came from, scattered
+/// across the filesystem, and there's no safe way to enumerate "Frame's"
+/// directories there without pattern-matching other people's files.
+fn scan_two_pass_logs(manager: &ConversionManager) -> Vec<OrphanedArtifact> {
+    match manager.current_output_settings().output_directory {
+        Some(dir) => scan_dir_for_two_pass_logs(&dir),
+        None => Vec::new(),
+    }
+}
+
+/// Failed conversions' recorded output paths that still exist on disk.
+/// Opt-in via `include_failed_outputs_in_orphan_scan`, since a kept partial
+/// output after a failure (`keep_partial_on_error`) is often intentional
+/// rather than an orphan.
+async fn scan_failed_history_outputs(manager: &ConversionManager) -> Vec<OrphanedArtifact> {
+    if !manager.current_include_failed_outputs_in_orphan_scan() {
+        return Vec::new();
+    }
+    let history = manager
+        .get_conversion_history(usize::MAX, 0)
+        .await
+        .unwrap_or_default();
+    history
+        .into_iter()
+        .filter(|entry| !entry.succeeded)
+        .filter_map(|entry| as_orphan(PathBuf::from(entry.output_path), "failed-output"))
+        .collect()
+}
+
+async fn collect_orphans(manager: &ConversionManager) -> Vec<OrphanedArtifact> {
+    let mut artifacts = scan_temp_dir();
+    artifacts.extend(scan_two_pass_logs(manager));
+    artifacts.extend(scan_failed_history_outputs(manager).await);
+    artifacts
+}
+
+/// Lists on-disk artifacts Frame recognizes as its own leftovers, without
+/// deleting anything.
+#[tauri::command]
+pub(crate) async fn list_orphaned_artifacts(
+    manager: tauri::State<'_, ConversionManager>,
+) -> Result<Vec<OrphanedArtifact>, ConversionError> {
+    Ok(collect_orphans(&manager).await)
+}
+
+/// Deletes every artifact `list_orphaned_artifacts` would report, returning
+/// only the ones actually removed — a file that vanished or became
+/// unreadable between the scan and the delete is silently skipped rather
+/// than failing the whole cleanup.
+#[tauri::command]
+pub(crate) async fn clean_orphaned_artifacts(
+    manager: tauri::State<'_, ConversionManager>,
+) -> Result<Vec<OrphanedArtifact>, ConversionError> {
+    let found = collect_orphans(&manager).await;
+    Ok(found
+        .into_iter()
+        .filter(|artifact| std::fs::remove_file(&artifact.path).is_ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn isolated_test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("frame_orphan_cleanup_test_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_as_orphan_returns_none_for_missing_file() {
+        let dir = isolated_test_dir("missing");
+        assert!(as_orphan(dir.join("nope.log"), "quality-temp-file").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_as_orphan_reports_kind_and_size_for_existing_file() {
+        let dir = isolated_test_dir("existing");
+        let path = dir.join("stats.log");
+        std::fs::write(&path, b"hello").unwrap();
+
+        let artifact = as_orphan(path, "quality-temp-file").unwrap();
+
+        assert_eq!(artifact.kind, "quality-temp-file");
+        assert_eq!(artifact.size_bytes, 5);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_dir_for_temp_artifacts_only_matches_frame_owned_names() {
+        let dir = isolated_test_dir("temp_artifacts");
+        std::fs::write(dir.join("frame_quality_abc123.log"), b"x").unwrap();
+        std::fs::write(dir.join("frame_concat_task-1.txt"), b"x").unwrap();
+        std::fs::write(dir.join("not_ours.log"), b"x").unwrap();
+        std::fs::write(dir.join("frame_quality_abc123.log.bak"), b"x").unwrap();
+
+        let found = scan_dir_for_temp_artifacts(&dir);
+
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().any(|a| a.kind == "quality-temp-file"));
+        assert!(found.iter().any(|a| a.kind == "concat-temp-file"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_dir_for_two_pass_logs_only_matches_ffmpeg2pass_prefix() {
+        let dir = isolated_test_dir("two_pass");
+        std::fs::write(dir.join("ffmpeg2pass-0.log"), b"x").unwrap();
+        std::fs::write(dir.join("ffmpeg2pass-0.log.mbtree"), b"x").unwrap();
+        std::fs::write(dir.join("movie_converted.mp4"), b"x").unwrap();
+
+        let found = scan_dir_for_two_pass_logs(&dir);
+
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|a| a.kind == "two-pass-log"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_dir_for_two_pass_logs_on_missing_dir_returns_empty() {
+        let dir = std::env::temp_dir().join("frame_orphan_cleanup_test_does_not_exist");
+        assert!(scan_dir_for_two_pass_logs(&dir).is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+//! Collects files the OS asked Frame to open (CLI launch arguments, or a
+//! macOS `open-file`/`Opened` event) that arrive before the splash screen
+//! has closed, so nothing is lost while the frontend isn't listening yet.
+//! Once ready, subsequent opens are forwarded straight through as
+//! `files-opened`; `take_pending_open_files` lets the frontend collect
+//! whatever piled up before that.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::single_instance::has_media_extension;
+
+/// Managed state holding paths collected before the frontend was ready to
+/// receive a `files-opened` event directly.
+#[derive(Default)]
+pub(crate) struct PendingOpenFiles(Mutex<Vec<String>>);
+
+impl PendingOpenFiles {
+    pub(crate) fn extend(&self, paths: Vec<String>) {
+        let mut pending = self.0.lock().unwrap();
+        for path in paths {
+            if !pending.contains(&path) {
+                pending.push(path);
+            }
+        }
+    }
+}
+
+/// Canonicalizes and existence-checks each path, dropping (and logging)
+/// anything that isn't a real file with an extension Frame knows how to
+/// convert, rather than crashing the launch over a stray argument.
+pub(crate) fn validate_paths(paths: Vec<String>) -> Vec<String> {
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            if !has_media_extension(&path) {
+                eprintln!("Ignoring non-media launch argument: {}", path);
+                return None;
+            }
+            match std::fs::canonicalize(&path) {
+                Ok(resolved) if resolved.is_file() => Some(resolved.to_string_lossy().to_string()),
+                Ok(_) => {
+                    eprintln!("Ignoring launch argument that isn't a file: {}", path);
+                    None
+                }
+                Err(e) => {
+                    eprintln!("Ignoring launch argument {}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Serialize)]
+struct FilesOpenedPayload {
+    paths: Vec<String>,
+}
+
+/// Routes a set of already-validated file paths: if the main window is up
+/// and visible (the splash has closed), forwards them immediately as a
+/// `files-opened` event and focuses the window; otherwise stashes them for
+/// `take_pending_open_files` to pick up once the frontend is ready.
+pub(crate) fn dispatch(app: &AppHandle, paths: Vec<String>) {
+    if paths.is_empty() {
+        return;
+    }
+
+    let window = app.get_webview_window("main");
+    let frontend_ready = window
+        .as_ref()
+        .and_then(|w| w.is_visible().ok())
+        .unwrap_or(false);
+
+    if frontend_ready {
+        if let Some(window) = &window {
+            let _ = window.set_focus();
+        }
+        let _ = app.emit("files-opened", FilesOpenedPayload { paths });
+    } else {
+        app.state::<PendingOpenFiles>().extend(paths);
+    }
+}
+
+#[tauri::command]
+pub(crate) fn take_pending_open_files(state: tauri::State<'_, PendingOpenFiles>) -> Vec<String> {
+    std::mem::take(&mut *state.0.lock().unwrap())
+}
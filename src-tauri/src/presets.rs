@@ -0,0 +1,636 @@
+//! Named encode presets, persisted as one `ConversionConfig` JSON file per
+//! preset under the app config dir so they survive a webview data wipe and
+//! can be validated the same way a queued task is. A handful of read-only
+//! built-in presets ship alongside whatever the user has saved; they're
+//! defined in code, never touch disk, and can't be deleted or renamed.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::conversion::{ConversionConfig, ConversionError, EncoderCache, validate_config};
+
+/// One saved preset as returned to the frontend: the config plus whether
+/// it's one of the built-ins (read-only, no backing file).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetEntry {
+    pub name: String,
+    pub built_in: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<ConversionConfig>,
+    /// Set instead of `config` when the preset's file on disk failed to
+    /// parse, so one corrupt file doesn't take down the whole listing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The read-only presets every install ships with. Defined here rather than
+/// as shipped JSON files so they can't drift from what this build of the app
+/// actually supports (codec names, container support) and never need a
+/// migration when their defaults change between versions.
+fn built_in_presets() -> Vec<(&'static str, ConversionConfig)> {
+    vec![
+        (
+            "Web 1080p",
+            ConversionConfig {
+                container: "mp4".into(),
+                video_codec: "libx264".into(),
+                video_bitrate_mode: "crf".into(),
+                video_bitrate: "5000".into(),
+                audio_codec: "aac".into(),
+                audio_bitrate: "128".into(),
+                audio_channels: "original".into(),
+                resolution: "1080p".into(),
+                scaling_algorithm: "bicubic".into(),
+                fps: "original".into(),
+                crf: 23,
+                preset: "medium".into(),
+                ..Default::default()
+            },
+        ),
+        (
+            "Discord 25MB",
+            // A fixed conservative bitrate rather than a true target-size
+            // solve — actual output size still depends on the source's
+            // duration, this just keeps a typical short clip under the
+            // limit without a two-pass bitrate calculation.
+            ConversionConfig {
+                container: "mp4".into(),
+                video_codec: "libx264".into(),
+                video_bitrate_mode: "bitrate".into(),
+                video_bitrate: "2000".into(),
+                audio_codec: "aac".into(),
+                audio_bitrate: "96".into(),
+                audio_channels: "original".into(),
+                resolution: "720p".into(),
+                scaling_algorithm: "bicubic".into(),
+                fps: "original".into(),
+                preset: "fast".into(),
+                ..Default::default()
+            },
+        ),
+        (
+            "Archive HEVC",
+            ConversionConfig {
+                container: "mkv".into(),
+                video_codec: "libx265".into(),
+                video_bitrate_mode: "crf".into(),
+                video_bitrate: "5000".into(),
+                audio_codec: "flac".into(),
+                audio_bitrate: "0".into(),
+                audio_channels: "original".into(),
+                resolution: "original".into(),
+                scaling_algorithm: "bicubic".into(),
+                fps: "original".into(),
+                crf: 18,
+                preset: "slow".into(),
+                ..Default::default()
+            },
+        ),
+        (
+            "Podcast MP3",
+            ConversionConfig {
+                container: "mp3".into(),
+                video_codec: "libx264".into(),
+                video_bitrate_mode: "crf".into(),
+                video_bitrate: "5000".into(),
+                audio_codec: "libmp3lame".into(),
+                audio_bitrate: "128".into(),
+                audio_channels: "mono".into(),
+                resolution: "original".into(),
+                scaling_algorithm: "bicubic".into(),
+                fps: "original".into(),
+                preset: "medium".into(),
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+fn is_built_in_name(name: &str) -> bool {
+    built_in_presets()
+        .iter()
+        .any(|(built_in_name, _)| built_in_name.eq_ignore_ascii_case(name))
+}
+
+fn presets_dir(app: &AppHandle) -> Result<PathBuf, ConversionError> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join("presets"))
+        .map_err(|e| ConversionError::Io(std::io::Error::other(e.to_string())))
+}
+
+/// Rejects preset names that are empty or would escape the presets directory
+/// once turned into a filename. Doesn't check for a built-in collision —
+/// `import_presets` needs to tell that case apart from a genuinely unsafe
+/// name, since a built-in collision can be resolved by renaming and this
+/// one can't.
+fn validate_preset_name_syntax(name: &str) -> Result<(), ConversionError> {
+    if name.trim().is_empty() {
+        return Err(ConversionError::InvalidInput(
+            "Preset name cannot be empty".to_string(),
+        ));
+    }
+    if name.contains('/') || name.contains('\\') || name.contains("..") {
+        return Err(ConversionError::InvalidInput(
+            "Preset name cannot contain path separators".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The full name check for a direct save: safe as a filename, and not
+/// shadowing a built-in preset.
+fn validate_preset_name(name: &str) -> Result<(), ConversionError> {
+    validate_preset_name_syntax(name)?;
+    if is_built_in_name(name) {
+        return Err(ConversionError::InvalidInput(format!(
+            "\"{}\" is a built-in preset and can't be overwritten",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn preset_file_path(app: &AppHandle, name: &str) -> Result<PathBuf, ConversionError> {
+    Ok(presets_dir(app)?.join(format!("{}.json", name)))
+}
+
+fn write_preset_file(
+    app: &AppHandle,
+    name: &str,
+    config: &ConversionConfig,
+) -> Result<(), ConversionError> {
+    let dir = presets_dir(app)?;
+    std::fs::create_dir_all(&dir)?;
+    let json = serde_json::to_string_pretty(config)?;
+    std::fs::write(preset_file_path(app, name)?, json)?;
+    Ok(())
+}
+
+/// Validates `config` and writes it to `name`'s preset file, creating or
+/// overwriting it. Rejects names that collide with a built-in preset.
+#[tauri::command]
+pub(crate) async fn save_preset(
+    app: AppHandle,
+    encoder_cache: tauri::State<'_, EncoderCache>,
+    name: String,
+    config: ConversionConfig,
+) -> Result<(), ConversionError> {
+    validate_preset_name(&name)?;
+    validate_config(&config, None, encoder_cache.snapshot().as_deref())?;
+    write_preset_file(&app, &name, &config)
+}
+
+/// Lists every preset: the built-ins first, then whatever's saved on disk.
+/// A preset file that fails to parse is reported as an entry with `error`
+/// set rather than failing the whole call.
+#[tauri::command]
+pub(crate) async fn list_presets(app: AppHandle) -> Result<Vec<PresetEntry>, ConversionError> {
+    let mut entries: Vec<PresetEntry> = built_in_presets()
+        .into_iter()
+        .map(|(name, config)| PresetEntry {
+            name: name.to_string(),
+            built_in: true,
+            config: Some(config),
+            error: None,
+        })
+        .collect();
+
+    let dir = presets_dir(&app)?;
+    let Ok(read_dir) = std::fs::read_dir(&dir) else {
+        return Ok(entries);
+    };
+
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+
+        match std::fs::read_to_string(&path)
+            .map_err(ConversionError::from)
+            .and_then(|contents| Ok(serde_json::from_str::<ConversionConfig>(&contents)?))
+        {
+            Ok(config) => entries.push(PresetEntry {
+                name,
+                built_in: false,
+                config: Some(config),
+                error: None,
+            }),
+            Err(e) => entries.push(PresetEntry {
+                name,
+                built_in: false,
+                config: None,
+                error: Some(e.to_string()),
+            }),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Removes `name`'s preset file. Rejects built-in names rather than
+/// silently no-op'ing, so the caller finds out the delete didn't happen.
+#[tauri::command]
+pub(crate) async fn delete_preset(app: AppHandle, name: String) -> Result<(), ConversionError> {
+    if is_built_in_name(&name) {
+        return Err(ConversionError::InvalidInput(format!(
+            "\"{}\" is a built-in preset and can't be deleted",
+            name
+        )));
+    }
+    let path = preset_file_path(&app, &name)?;
+    if !path.is_file() {
+        return Err(ConversionError::InvalidInput(format!(
+            "No preset named \"{}\"",
+            name
+        )));
+    }
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Renames `old`'s preset file to `new`. Neither name may be a built-in, and
+/// `new` must not already be taken by another saved preset.
+#[tauri::command]
+pub(crate) async fn rename_preset(
+    app: AppHandle,
+    old: String,
+    new: String,
+) -> Result<(), ConversionError> {
+    if is_built_in_name(&old) {
+        return Err(ConversionError::InvalidInput(format!(
+            "\"{}\" is a built-in preset and can't be renamed",
+            old
+        )));
+    }
+    validate_preset_name(&new)?;
+
+    let old_path = preset_file_path(&app, &old)?;
+    if !old_path.is_file() {
+        return Err(ConversionError::InvalidInput(format!(
+            "No preset named \"{}\"",
+            old
+        )));
+    }
+    let new_path = preset_file_path(&app, &new)?;
+    if new_path.is_file() {
+        return Err(ConversionError::InvalidInput(format!(
+            "A preset named \"{}\" already exists",
+            new
+        )));
+    }
+    std::fs::rename(old_path, new_path)?;
+    Ok(())
+}
+
+/// Bumped whenever a breaking change is made to the bundle layout itself
+/// (not to `ConversionConfig`'s fields — those are versioned by serde's own
+/// `#[serde(default)]` tolerance, since the config is stored as a raw
+/// `Value` and only parsed into a typed struct on import).
+const PRESET_BUNDLE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PresetBundle {
+    format_version: u32,
+    presets: Vec<PresetBundleEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PresetBundleEntry {
+    name: String,
+    config: serde_json::Value,
+}
+
+/// Writes `names` out to a single shareable JSON bundle at `path`. Built-in
+/// presets can be exported (there's nothing sensitive about them), they'll
+/// just re-import as ordinary user presets on the other end.
+#[tauri::command]
+pub(crate) async fn export_presets(
+    app: AppHandle,
+    path: String,
+    names: Vec<String>,
+) -> Result<(), ConversionError> {
+    let all = list_presets(app.clone()).await?;
+    let mut presets = Vec::with_capacity(names.len());
+    for name in &names {
+        let entry = all.iter().find(|e| &e.name == name).ok_or_else(|| {
+            ConversionError::InvalidInput(format!("No preset named \"{}\"", name))
+        })?;
+        let config = entry.config.as_ref().ok_or_else(|| {
+            ConversionError::InvalidInput(format!(
+                "Preset \"{}\" could not be read: {}",
+                name,
+                entry.error.as_deref().unwrap_or("unknown error")
+            ))
+        })?;
+        presets.push(PresetBundleEntry {
+            name: entry.name.clone(),
+            config: serde_json::to_value(config)?,
+        });
+    }
+
+    let bundle = PresetBundle {
+        format_version: PRESET_BUNDLE_FORMAT_VERSION,
+        presets,
+    };
+    std::fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+    Ok(())
+}
+
+/// How `import_presets` should handle a name that's already taken.
+#[derive(Debug, Clone, Copy)]
+enum ImportCollisionPolicy {
+    Skip,
+    Rename,
+    Overwrite,
+}
+
+impl ImportCollisionPolicy {
+    fn parse(name: &str) -> Result<Self, ConversionError> {
+        match name.to_lowercase().as_str() {
+            "skip" => Ok(Self::Skip),
+            "rename" => Ok(Self::Rename),
+            "overwrite" => Ok(Self::Overwrite),
+            _ => Err(ConversionError::InvalidInput(format!(
+                "Unsupported overwrite policy: {}",
+                name
+            ))),
+        }
+    }
+}
+
+/// The outcome of importing one bundle entry.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    pub requested_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The first name in the `base (2)`, `base (3)`, ... sequence not already
+/// taken by an existing or a built-in preset, mirroring the
+/// `resolve_collision` convention `conversion.rs` uses for output filenames.
+/// `base` itself is never returned: `import_presets` only calls this once
+/// `base` has collided.
+fn unique_preset_name(base: &str, existing: &[String]) -> String {
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{} ({})", base, counter);
+        let taken = is_built_in_name(&candidate)
+            || existing.iter().any(|n| n.eq_ignore_ascii_case(&candidate));
+        if !taken {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Imports every preset in the bundle at `path`, validating each config
+/// against the current schema (unknown fields are tolerated, but a field
+/// with no default that's missing entirely is rejected) and resolving name
+/// collisions per `overwrite_policy` ("skip"/"rename"/"overwrite"). One bad
+/// entry doesn't fail the rest — every entry gets its own result.
+#[tauri::command]
+pub(crate) async fn import_presets(
+    app: AppHandle,
+    encoder_cache: tauri::State<'_, EncoderCache>,
+    path: String,
+    overwrite_policy: String,
+) -> Result<Vec<ImportResult>, ConversionError> {
+    let policy = ImportCollisionPolicy::parse(&overwrite_policy)?;
+    let contents = std::fs::read_to_string(&path)?;
+    let bundle: PresetBundle = serde_json::from_str(&contents)
+        .map_err(|e| ConversionError::InvalidInput(format!("Not a valid preset bundle: {}", e)))?;
+
+    let mut existing: Vec<String> = list_presets(app.clone())
+        .await?
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+    let mut results = Vec::with_capacity(bundle.presets.len());
+
+    for entry in bundle.presets {
+        let requested_name = entry.name;
+
+        if let Err(e) = validate_preset_name_syntax(&requested_name) {
+            results.push(ImportResult {
+                requested_name,
+                saved_name: None,
+                error: Some(e.to_string()),
+            });
+            continue;
+        }
+
+        let config: ConversionConfig = match serde_json::from_value(entry.config) {
+            Ok(config) => config,
+            Err(e) => {
+                results.push(ImportResult {
+                    requested_name,
+                    saved_name: None,
+                    error: Some(format!("Invalid preset config: {}", e)),
+                });
+                continue;
+            }
+        };
+        if let Err(e) = validate_config(&config, None, encoder_cache.snapshot().as_deref()) {
+            results.push(ImportResult {
+                requested_name,
+                saved_name: None,
+                error: Some(e.to_string()),
+            });
+            continue;
+        }
+
+        let collides = is_built_in_name(&requested_name)
+            || existing
+                .iter()
+                .any(|n| n.eq_ignore_ascii_case(&requested_name));
+        let target_name = if !collides {
+            requested_name.clone()
+        } else {
+            match policy {
+                ImportCollisionPolicy::Skip => {
+                    results.push(ImportResult {
+                        requested_name,
+                        saved_name: None,
+                        error: Some("Skipped: a preset with this name already exists".to_string()),
+                    });
+                    continue;
+                }
+                ImportCollisionPolicy::Rename => unique_preset_name(&requested_name, &existing),
+                ImportCollisionPolicy::Overwrite => {
+                    if is_built_in_name(&requested_name) {
+                        results.push(ImportResult {
+                            requested_name: requested_name.clone(),
+                            saved_name: None,
+                            error: Some(format!(
+                                "\"{}\" is a built-in preset and can't be overwritten",
+                                requested_name
+                            )),
+                        });
+                        continue;
+                    }
+                    requested_name.clone()
+                }
+            }
+        };
+
+        match write_preset_file(&app, &target_name, &config) {
+            Ok(()) => {
+                existing.push(target_name.clone());
+                results.push(ImportResult {
+                    requested_name,
+                    saved_name: Some(target_name),
+                    error: None,
+                });
+            }
+            Err(e) => results.push(ImportResult {
+                requested_name,
+                saved_name: None,
+                error: Some(e.to_string()),
+            }),
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_presets_have_unique_names() {
+        let names: Vec<&str> = built_in_presets().iter().map(|(name, _)| *name).collect();
+        let mut deduped = names.clone();
+        deduped.sort();
+        deduped.dedup();
+        assert_eq!(names.len(), deduped.len());
+    }
+
+    #[test]
+    fn test_is_built_in_name_is_case_insensitive() {
+        assert!(is_built_in_name("web 1080p"));
+        assert!(is_built_in_name("WEB 1080P"));
+        assert!(!is_built_in_name("My Custom Preset"));
+    }
+
+    #[test]
+    fn test_validate_preset_name_rejects_path_traversal() {
+        assert!(validate_preset_name("../escape").is_err());
+        assert!(validate_preset_name("sub/dir").is_err());
+        assert!(validate_preset_name("sub\\dir").is_err());
+    }
+
+    #[test]
+    fn test_validate_preset_name_rejects_built_in_and_empty() {
+        assert!(validate_preset_name("Archive HEVC").is_err());
+        assert!(validate_preset_name("   ").is_err());
+        assert!(validate_preset_name("My Preset").is_ok());
+    }
+
+    #[test]
+    fn test_import_collision_policy_parse_is_case_insensitive() {
+        assert!(matches!(
+            ImportCollisionPolicy::parse("Skip").unwrap(),
+            ImportCollisionPolicy::Skip
+        ));
+        assert!(matches!(
+            ImportCollisionPolicy::parse("RENAME").unwrap(),
+            ImportCollisionPolicy::Rename
+        ));
+        assert!(ImportCollisionPolicy::parse("bogus").is_err());
+    }
+
+    #[test]
+    fn test_unique_preset_name_skips_taken_and_built_in_names() {
+        let existing = vec!["My Preset (2)".to_string()];
+        assert_eq!(unique_preset_name("Web 1080p", &existing), "Web 1080p (2)");
+
+        let existing = vec!["My Preset (2)".to_string(), "My Preset (3)".to_string()];
+        assert_eq!(unique_preset_name("My Preset", &existing), "My Preset (4)");
+    }
+
+    #[test]
+    fn test_preset_bundle_round_trip() {
+        let bundle = PresetBundle {
+            format_version: PRESET_BUNDLE_FORMAT_VERSION,
+            presets: vec![PresetBundleEntry {
+                name: "My Preset".to_string(),
+                config: serde_json::to_value(&built_in_presets()[0].1).unwrap(),
+            }],
+        };
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        let round_tripped: PresetBundle = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(round_tripped.format_version, PRESET_BUNDLE_FORMAT_VERSION);
+        assert_eq!(round_tripped.presets.len(), 1);
+        assert_eq!(round_tripped.presets[0].name, "My Preset");
+        let config: ConversionConfig =
+            serde_json::from_value(round_tripped.presets[0].config.clone()).unwrap();
+        assert_eq!(config.container, built_in_presets()[0].1.container);
+    }
+
+    #[test]
+    fn test_config_from_older_schema_missing_optional_fields_still_parses() {
+        // Only the fields with no `#[serde(default)]` are required; everything
+        // else (added in later versions) is missing here on purpose, the way
+        // a bundle exported by an older build of the app would look.
+        let json = r#"{
+            "container": "mp4",
+            "videoCodec": "libx264",
+            "videoBitrateMode": "crf",
+            "videoBitrate": "5000",
+            "audioCodec": "aac",
+            "audioBitrate": "128",
+            "audioChannels": "original",
+            "selectedAudioTracks": [],
+            "resolution": "original",
+            "customWidth": null,
+            "customHeight": null,
+            "scalingAlgorithm": "bicubic",
+            "fps": "original",
+            "crf": 23,
+            "preset": "medium"
+        }"#;
+
+        let config: ConversionConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.container, "mp4");
+        assert_eq!(config.video_codec, "libx264");
+        assert!(!config.lossless);
+        assert!(!config.allow_upscale);
+    }
+
+    #[test]
+    fn test_config_missing_required_field_is_rejected() {
+        let json = r#"{
+            "videoCodec": "libx264",
+            "videoBitrateMode": "crf",
+            "videoBitrate": "5000",
+            "audioCodec": "aac",
+            "audioBitrate": "128",
+            "audioChannels": "original",
+            "selectedAudioTracks": [],
+            "resolution": "original",
+            "customWidth": null,
+            "customHeight": null,
+            "scalingAlgorithm": "bicubic",
+            "fps": "original",
+            "crf": 23,
+            "preset": "medium"
+        }"#;
+
+        assert!(serde_json::from_str::<ConversionConfig>(json).is_err());
+    }
+}
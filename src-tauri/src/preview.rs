@@ -0,0 +1,192 @@
+//! Renders a short preview clip through the exact same `build_ffmpeg_args`
+//! pipeline the real encode would use, so a config can be sanity-checked
+//! before committing to a multi-hour conversion. Runs outside
+//! `ConversionManager`'s queue since it's a one-off job that shouldn't
+//! consume a concurrency slot.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::ShellExt;
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+
+use crate::conversion::{ConversionConfig, ConversionError, TrimSegment, build_ffmpeg_args};
+
+/// Managed state tracking in-flight `generate_preview` ffmpeg processes by
+/// caller-supplied id, so `cancel_preview` can kill one before it finishes.
+#[derive(Default)]
+pub(crate) struct PreviewJobs(Mutex<HashMap<String, CommandChild>>);
+
+fn preview_cache_dir(app: &AppHandle) -> Result<PathBuf, ConversionError> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join("previews"))
+        .map_err(|e| ConversionError::Shell(e.to_string()))
+}
+
+/// Deletes every cached preview clip. Called once at app start: previews are
+/// keyed by file+config rather than content-addressed, so a leftover from a
+/// previous run is never reusable and just wastes disk space.
+pub(crate) fn clear_preview_cache(app: &AppHandle) {
+    if let Ok(dir) = preview_cache_dir(app) {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
+
+/// Formats seconds as `HH:MM:SS.mmm`, the timestamp format [`TrimSegment`]
+/// expects.
+pub(crate) fn format_timestamp(seconds: f64) -> String {
+    let total_millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    let hours = total_millis / 3_600_000;
+    let minutes = (total_millis / 60_000) % 60;
+    let secs = (total_millis / 1_000) % 60;
+    let millis = total_millis % 1_000;
+    format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, secs, millis)
+}
+
+/// A new preview for the same file/config overwrites the previous one at the
+/// same output path, so the cache key only needs to vary by file and config,
+/// not by the requested preview window.
+fn preview_cache_key(file_path: &str, config: &ConversionConfig) -> String {
+    let mut hasher = DefaultHasher::new();
+    file_path.hash(&mut hasher);
+    serde_json::to_string(config)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Renders a `duration_secs`-long preview of `file_path` starting at
+/// `at_seconds`, using `config` exactly as the real encode would (segments
+/// are overridden to just this window, and `accurate_trim` is forced on so
+/// the preview actually starts where asked, at the cost of the fast-seek
+/// path). Pass `id` to [`cancel_preview`] to kill it before it finishes.
+#[tauri::command]
+pub(crate) async fn generate_preview(
+    app: AppHandle,
+    jobs: tauri::State<'_, PreviewJobs>,
+    id: String,
+    file_path: String,
+    config: ConversionConfig,
+    at_seconds: f64,
+    duration_secs: f64,
+) -> Result<String, ConversionError> {
+    let cache_dir = preview_cache_dir(&app)?;
+    std::fs::create_dir_all(&cache_dir).map_err(|e| {
+        ConversionError::Shell(format!("Failed to create preview cache dir: {}", e))
+    })?;
+
+    let output_path = cache_dir.join(format!(
+        "{}.{}",
+        preview_cache_key(&file_path, &config),
+        config.container
+    ));
+
+    let mut preview_config = config;
+    preview_config.accurate_trim = true;
+    preview_config.segments = vec![TrimSegment {
+        start: format_timestamp(at_seconds),
+        end: format_timestamp(at_seconds + duration_secs.max(0.0)),
+    }];
+
+    let args = build_ffmpeg_args(
+        &file_path,
+        &output_path.to_string_lossy(),
+        &preview_config,
+        None,
+        &[],
+    );
+
+    let cmd = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .args(args);
+
+    let (mut rx, child) = cmd
+        .spawn()
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+    jobs.0.lock().unwrap().insert(id.clone(), child);
+
+    let mut exit_code = None;
+    let mut stderr_tail = String::new();
+    while let Some(event) = rx.recv().await {
+        match event {
+            CommandEvent::Stderr(bytes) => {
+                let line = String::from_utf8_lossy(&bytes);
+                if !line.trim().is_empty() {
+                    stderr_tail = line.trim().to_string();
+                }
+            }
+            CommandEvent::Terminated(payload) => exit_code = payload.code,
+            _ => {}
+        }
+    }
+    let cancelled = jobs.0.lock().unwrap().remove(&id).is_none();
+
+    if cancelled {
+        return Err(ConversionError::Cancelled(id));
+    }
+    if exit_code != Some(0) {
+        return Err(ConversionError::Shell(if stderr_tail.is_empty() {
+            "ffmpeg failed to render the preview".to_string()
+        } else {
+            stderr_tail
+        }));
+    }
+
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// Kills the in-flight `generate_preview` run registered under `id`, if any
+/// is still running; a no-op if it already finished or was never started.
+#[tauri::command]
+pub(crate) fn cancel_preview(
+    jobs: tauri::State<'_, PreviewJobs>,
+    id: String,
+) -> Result<(), ConversionError> {
+    if let Some(child) = jobs.0.lock().unwrap().remove(&id) {
+        let _ = child.kill();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp_formats_hms_millis() {
+        assert_eq!(format_timestamp(3661.5), "01:01:01.500");
+    }
+
+    #[test]
+    fn test_format_timestamp_handles_zero() {
+        assert_eq!(format_timestamp(0.0), "00:00:00.000");
+    }
+
+    #[test]
+    fn test_format_timestamp_clamps_negative_to_zero() {
+        assert_eq!(format_timestamp(-5.0), "00:00:00.000");
+    }
+
+    #[test]
+    fn test_preview_cache_key_is_deterministic() {
+        let config = ConversionConfig::default();
+        let a = preview_cache_key("clip.mp4", &config);
+        let b = preview_cache_key("clip.mp4", &config);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_preview_cache_key_changes_with_config() {
+        let mut config = ConversionConfig::default();
+        let a = preview_cache_key("clip.mp4", &config);
+        config.video_codec = "libx265".to_string();
+        assert_ne!(a, preview_cache_key("clip.mp4", &config));
+    }
+}
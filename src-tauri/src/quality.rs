@@ -0,0 +1,345 @@
+//! Objective quality comparison between an original and an encoded output,
+//! run through ffmpeg's `ssim`/`psnr`/`libvmaf` filters into `-f null -` so
+//! no output file is ever written — only the aggregate score (and per-frame
+//! min/max) that ffmpeg prints get kept.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+
+use crate::conversion::ConversionError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QualityMetric {
+    Ssim,
+    Psnr,
+    Vmaf,
+}
+
+impl QualityMetric {
+    fn parse(name: &str) -> Result<Self, ConversionError> {
+        match name.to_lowercase().as_str() {
+            "ssim" => Ok(Self::Ssim),
+            "psnr" => Ok(Self::Psnr),
+            "vmaf" => Ok(Self::Vmaf),
+            _ => Err(ConversionError::InvalidInput(format!(
+                "Unsupported quality metric: {}",
+                name
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Ssim => "ssim",
+            Self::Psnr => "psnr",
+            Self::Vmaf => "vmaf",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QualityScore {
+    pub metric: String,
+    pub score: f64,
+    pub per_frame_min: f64,
+    pub per_frame_max: f64,
+}
+
+/// Whether this build of ffmpeg was compiled with the `libvmaf` filter,
+/// gating the "vmaf" metric to builds that actually support it instead of
+/// failing deep inside a filtergraph parse error.
+async fn has_libvmaf_filter(app: &AppHandle) -> bool {
+    let Ok(cmd) = app.shell().sidecar("ffmpeg") else {
+        return false;
+    };
+    match cmd.args(["-hide_banner", "-filters"]).output().await {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).contains("libvmaf"),
+        Err(_) => false,
+    }
+}
+
+/// Parses ffmpeg's aggregate SSIM stderr line, e.g. `SSIM Y:0.981095
+/// (17.234115) U:0.987562 (19.061631) V:0.987001 (18.859917) All:0.983359
+/// (17.783041)`, returning the overall `All` score.
+fn parse_ssim_aggregate(stderr: &str) -> Option<f64> {
+    let line = stderr.lines().find(|l| l.contains("SSIM"))?;
+    line.split("All:")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Parses one line of an SSIM `stats_file` (`n:1 Y:0.988232 U:0.992861
+/// V:0.993146 All:0.990593 (20.259696)`), returning that frame's `All` score.
+fn parse_ssim_stats_line(line: &str) -> Option<f64> {
+    line.split("All:")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Parses ffmpeg's aggregate PSNR stderr line, e.g. `PSNR y:34.213771
+/// u:41.376104 v:41.900434 average:35.871738 min:26.847492 max:60.000000`.
+/// Unlike SSIM, this line already reports the true per-frame min/max
+/// alongside the overall average, so no separate stats file is needed.
+fn parse_psnr_aggregate(stderr: &str) -> Option<(f64, f64, f64)> {
+    let line = stderr.lines().find(|l| l.contains("PSNR"))?;
+    let field = |name: &str| -> Option<f64> {
+        line.split(&format!("{}:", name))
+            .nth(1)?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    };
+    Some((field("average")?, field("min")?, field("max")?))
+}
+
+/// Parses the `pooled_metrics.vmaf` block out of a libvmaf `log_fmt=json`
+/// report, returning `(mean, min, max)`.
+fn parse_vmaf_json(json: &str) -> Option<(f64, f64, f64)> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let vmaf = value.get("pooled_metrics")?.get("vmaf")?;
+    Some((
+        vmaf.get("mean")?.as_f64()?,
+        vmaf.get("min")?.as_f64()?,
+        vmaf.get("max")?.as_f64()?,
+    ))
+}
+
+/// A collision-resistant, per-call temp path for the stats file ssim/libvmaf
+/// write their per-frame data to; unique per (paths, timestamp) so two
+/// concurrent comparisons of the same pair of files don't clobber each
+/// other's stats file mid-run.
+fn quality_stats_path(original_path: &str, encoded_path: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    original_path.hash(&mut hasher);
+    encoded_path.hash(&mut hasher);
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .hash(&mut hasher);
+    std::env::temp_dir().join(format!("frame_quality_{:016x}.log", hasher.finish()))
+}
+
+/// Escapes a path before it's interpolated into a filtergraph option value
+/// (e.g. `ssim=stats_file=...`): backslash first (so the later escapes
+/// aren't themselves re-escaped), then `:`, which ffmpeg's filtergraph
+/// parser otherwise reads as the next `key=value` separator. On Windows,
+/// `std::env::temp_dir()` returns an absolute path like `C:\Users\...`, and
+/// without this the drive-letter colon would break the parse.
+fn escape_filter_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(':', "\\:")
+        .replace('\'', "\\'")
+}
+
+/// Compares `encoded_path` against `original_path` using `metric`
+/// ("ssim"/"psnr"/"vmaf"), returning the aggregate score plus the per-frame
+/// min/max across the whole comparison.
+///
+/// `offset_secs` re-aligns the two inputs when the encode was trimmed: it
+/// seeks `original_path` forward by that amount before comparing, so frame 0
+/// of the encode lines up with the same moment in the source. `sample_every`
+/// (when `> 1`) compares only every Nth frame via a `select` filter, trading
+/// precision for runtime on long files.
+#[tauri::command]
+pub(crate) async fn compare_quality(
+    app: AppHandle,
+    original_path: String,
+    encoded_path: String,
+    metric: String,
+    offset_secs: Option<f64>,
+    sample_every: Option<u32>,
+) -> Result<QualityScore, ConversionError> {
+    let metric = QualityMetric::parse(&metric)?;
+    if metric == QualityMetric::Vmaf && !has_libvmaf_filter(&app).await {
+        return Err(ConversionError::InvalidInput(
+            "This build of ffmpeg wasn't compiled with libvmaf".to_string(),
+        ));
+    }
+
+    let select = sample_every
+        .filter(|n| *n > 1)
+        .map(|n| format!("select='not(mod(n\\,{}))',", n))
+        .unwrap_or_default();
+
+    let stats_path = quality_stats_path(&original_path, &encoded_path);
+    let stats_path_str = stats_path.to_string_lossy().to_string();
+    let stats_filter_value = escape_filter_value(&stats_path_str);
+
+    let filter = match metric {
+        QualityMetric::Ssim => format!(
+            "[0:v]{select}setpts=PTS-STARTPTS[main];[1:v]{select}setpts=PTS-STARTPTS[ref];[main][ref]ssim=stats_file={stats}",
+            select = select,
+            stats = stats_filter_value
+        ),
+        QualityMetric::Psnr => format!(
+            "[0:v]{select}setpts=PTS-STARTPTS[main];[1:v]{select}setpts=PTS-STARTPTS[ref];[main][ref]psnr",
+            select = select
+        ),
+        QualityMetric::Vmaf => format!(
+            "[0:v]{select}setpts=PTS-STARTPTS[main];[1:v]{select}setpts=PTS-STARTPTS[ref];[main][ref]libvmaf=log_path={stats}:log_fmt=json",
+            select = select,
+            stats = stats_filter_value
+        ),
+    };
+
+    let mut args = vec!["-i".to_string(), encoded_path];
+    if let Some(offset) = offset_secs.filter(|o| *o > 0.0) {
+        args.push("-ss".to_string());
+        args.push(format!("{:.3}", offset));
+    }
+    args.push("-i".to_string());
+    args.push(original_path);
+    args.push("-filter_complex".to_string());
+    args.push(filter);
+    args.push("-f".to_string());
+    args.push("null".to_string());
+    args.push("-".to_string());
+
+    let cmd = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .args(args);
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    let result = match metric {
+        QualityMetric::Ssim => {
+            let score = parse_ssim_aggregate(&stderr)
+                .ok_or_else(|| ConversionError::Probe("Could not parse SSIM output".to_string()))?;
+            let frame_scores: Vec<f64> = std::fs::read_to_string(&stats_path)
+                .map(|contents| contents.lines().filter_map(parse_ssim_stats_line).collect())
+                .unwrap_or_default();
+            let per_frame_min = frame_scores.iter().copied().fold(f64::INFINITY, f64::min);
+            let per_frame_max = frame_scores
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max);
+            QualityScore {
+                metric: metric.as_str().to_string(),
+                score,
+                per_frame_min: if per_frame_min.is_finite() {
+                    per_frame_min
+                } else {
+                    score
+                },
+                per_frame_max: if per_frame_max.is_finite() {
+                    per_frame_max
+                } else {
+                    score
+                },
+            }
+        }
+        QualityMetric::Psnr => {
+            let (average, min, max) = parse_psnr_aggregate(&stderr)
+                .ok_or_else(|| ConversionError::Probe("Could not parse PSNR output".to_string()))?;
+            QualityScore {
+                metric: metric.as_str().to_string(),
+                score: average,
+                per_frame_min: min,
+                per_frame_max: max,
+            }
+        }
+        QualityMetric::Vmaf => {
+            let json = std::fs::read_to_string(&stats_path)
+                .map_err(|e| ConversionError::Probe(format!("Could not read VMAF log: {}", e)))?;
+            let (mean, min, max) = parse_vmaf_json(&json)
+                .ok_or_else(|| ConversionError::Probe("Could not parse VMAF output".to_string()))?;
+            QualityScore {
+                metric: metric.as_str().to_string(),
+                score: mean,
+                per_frame_min: min,
+                per_frame_max: max,
+            }
+        }
+    };
+
+    let _ = std::fs::remove_file(&stats_path);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ssim_aggregate() {
+        let stderr = "[Parsed_ssim_2 @ 0x55d2] SSIM Y:0.981095 (17.234115) U:0.987562 (19.061631) V:0.987001 (18.859917) All:0.983359 (17.783041)\n";
+        assert_eq!(parse_ssim_aggregate(stderr), Some(0.983359));
+    }
+
+    #[test]
+    fn test_parse_ssim_aggregate_missing_line_returns_none() {
+        assert_eq!(parse_ssim_aggregate("frame=  100 fps=30\n"), None);
+    }
+
+    #[test]
+    fn test_parse_ssim_stats_line() {
+        let line = "n:1 Y:0.988232 U:0.992861 V:0.993146 All:0.990593 (20.259696)";
+        assert_eq!(parse_ssim_stats_line(line), Some(0.990593));
+    }
+
+    #[test]
+    fn test_parse_psnr_aggregate() {
+        let stderr = "[Parsed_psnr_2 @ 0x55e3] PSNR y:34.213771 u:41.376104 v:41.900434 average:35.871738 min:26.847492 max:60.000000\n";
+        assert_eq!(
+            parse_psnr_aggregate(stderr),
+            Some((35.871738, 26.847492, 60.0))
+        );
+    }
+
+    #[test]
+    fn test_parse_psnr_aggregate_missing_line_returns_none() {
+        assert_eq!(parse_psnr_aggregate("frame=  100 fps=30\n"), None);
+    }
+
+    #[test]
+    fn test_parse_vmaf_json() {
+        let json = r#"{"version": "2.3.1", "pooled_metrics": {"vmaf": {"min": 82.5, "max": 99.1, "mean": 94.203437, "harmonic_mean": 94.1}}}"#;
+        assert_eq!(parse_vmaf_json(json), Some((94.203437, 82.5, 99.1)));
+    }
+
+    #[test]
+    fn test_parse_vmaf_json_missing_field_returns_none() {
+        assert_eq!(parse_vmaf_json(r#"{"version": "2.3.1"}"#), None);
+    }
+
+    #[test]
+    fn test_quality_metric_parse_is_case_insensitive() {
+        assert_eq!(QualityMetric::parse("SSIM").unwrap(), QualityMetric::Ssim);
+        assert_eq!(QualityMetric::parse("Vmaf").unwrap(), QualityMetric::Vmaf);
+        assert!(QualityMetric::parse("bogus").is_err());
+    }
+
+    #[test]
+    fn test_escape_filter_value_escapes_windows_drive_letter_colon() {
+        assert_eq!(
+            escape_filter_value(r"C:\Users\name\AppData\Local\Temp\frame_quality_1.log"),
+            r"C\:\\Users\\name\\AppData\\Local\\Temp\\frame_quality_1.log"
+        );
+    }
+
+    #[test]
+    fn test_escape_filter_value_escapes_quotes() {
+        assert_eq!(escape_filter_value("it's.log"), r"it\'s.log");
+    }
+}
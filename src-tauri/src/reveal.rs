@@ -0,0 +1,90 @@
+//! Selects a completed output file in the OS's file manager. macOS and
+//! Windows can select the file directly; Linux has no portable "select this
+//! file" primitive across desktop environments, so it falls back to opening
+//! the containing folder via the opener plugin.
+
+use std::path::Path;
+
+use tauri::AppHandle;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use tauri_plugin_opener::OpenerExt;
+
+use crate::conversion::ConversionError;
+
+#[cfg(target_os = "macos")]
+fn reveal_path(_app: &AppHandle, path: &str) -> Result<(), ConversionError> {
+    std::process::Command::new("open")
+        .args(["-R", path])
+        .spawn()
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+    Ok(())
+}
+
+/// Builds the single raw `/select,<path>` token `explorer.exe` expects.
+/// Explorer parses its own command line rather than argv, so a path
+/// containing spaces or commas has to be quoted as part of that one token —
+/// passing the path as a separate `.arg()` would have Rust quote it the
+/// *standard* way, which explorer's ad-hoc parser doesn't understand and
+/// which breaks on an embedded comma either way.
+#[cfg(target_os = "windows")]
+fn windows_select_arg(path: &str) -> String {
+    format!("/select,\"{}\"", path)
+}
+
+#[cfg(target_os = "windows")]
+fn reveal_path(_app: &AppHandle, path: &str) -> Result<(), ConversionError> {
+    use std::os::windows::process::CommandExt;
+
+    std::process::Command::new("explorer")
+        .raw_arg(windows_select_arg(path))
+        .spawn()
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn reveal_path(app: &AppHandle, path: &str) -> Result<(), ConversionError> {
+    let parent = Path::new(path).parent().unwrap_or_else(|| Path::new("/"));
+    app.opener()
+        .open_path(parent.to_string_lossy().to_string(), None::<&str>)
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+    Ok(())
+}
+
+/// Reveals `path` in the platform's file manager, first checking it still
+/// exists so a stale "Show in folder" click on a moved or deleted output
+/// fails with a clear error instead of silently doing nothing.
+#[tauri::command]
+pub(crate) async fn reveal_in_file_manager(
+    app: AppHandle,
+    path: String,
+) -> Result<(), ConversionError> {
+    if !Path::new(&path).exists() {
+        return Err(ConversionError::PathNotFound(path));
+    }
+    reveal_path(&app, &path)
+}
+
+#[cfg(all(test, target_os = "windows"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_select_arg_quotes_the_path() {
+        assert_eq!(
+            windows_select_arg(r"C:\Users\a\clip.mp4"),
+            r#"/select,"C:\Users\a\clip.mp4""#
+        );
+    }
+
+    #[test]
+    fn test_windows_select_arg_survives_commas_and_spaces() {
+        let path = r"C:\Users\a\My Videos, Trip 2024\clip.mp4";
+        let arg = windows_select_arg(path);
+        assert_eq!(arg, format!("/select,\"{}\"", path));
+        // The comma and spaces must stay inside the quoted path segment,
+        // not split it into separate tokens.
+        assert!(arg.starts_with("/select,\""));
+        assert!(arg.ends_with('"'));
+    }
+}
@@ -0,0 +1,367 @@
+//! Calibrated output-size estimation: instead of reading a bits-per-pixel
+//! curve like `estimation`, this actually encodes a few short windows of the
+//! file with the real config and measures what they produce, then
+//! extrapolates that measured rate to the full (trim-aware) duration. Slower
+//! and requires the real sidecar, but immune to the curve-based estimate's
+//! blind spots (hardware encoders, unusual content, two-pass logic). Runs
+//! outside `ConversionManager`'s queue, like `preview`, since it's a one-off
+//! job rather than a queued conversion.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tauri_plugin_shell::ShellExt;
+
+use crate::conversion::{
+    build_ffmpeg_args, effective_trim_duration, parse_time, probe_media, trim_duration,
+    ConversionConfig, ConversionError, TrimSegment,
+};
+use crate::preview::format_timestamp;
+
+/// Length of each sample window. Long enough for the encoder to settle past
+/// its startup ramp, short enough that three samples stay fast.
+const SAMPLE_WINDOW_SECS: f64 = 5.0;
+
+/// Managed state tracking the in-flight `estimate_output_accurate` ffmpeg
+/// process for a given caller id, so `cancel_estimate_output_accurate` can
+/// kill it mid-sample. Only one child is ever registered at a time, since
+/// samples encode one after another.
+#[derive(Default)]
+pub(crate) struct SampleEstimateJobs(Mutex<HashMap<String, CommandChild>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum EstimateConfidence {
+    High,
+    Medium,
+    Low,
+}
+
+/// How much the per-sample bitrates agree with each other, as a rough proxy
+/// for how much the extrapolated size estimate can be trusted: samples taken
+/// from wildly different parts of a file (e.g. a quiet scene vs. an action
+/// scene) will disagree more than a uniformly-encoded clip.
+fn confidence_from_samples(bitrates_kbps: &[f64]) -> EstimateConfidence {
+    if bitrates_kbps.len() < 2 {
+        return EstimateConfidence::Low;
+    }
+    let mean = bitrates_kbps.iter().sum::<f64>() / bitrates_kbps.len() as f64;
+    if mean <= 0.0 {
+        return EstimateConfidence::Low;
+    }
+    let variance = bitrates_kbps
+        .iter()
+        .map(|b| (b - mean).powi(2))
+        .sum::<f64>()
+        / bitrates_kbps.len() as f64;
+    let coefficient_of_variation = variance.sqrt() / mean;
+
+    if coefficient_of_variation < 0.15 {
+        EstimateConfidence::High
+    } else if coefficient_of_variation < 0.4 {
+        EstimateConfidence::Medium
+    } else {
+        EstimateConfidence::Low
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SampleEstimateResult {
+    pub size_bytes: u64,
+    pub per_sample_bitrates_kbps: Vec<f64>,
+    pub confidence: EstimateConfidence,
+}
+
+/// Resolves the source range samples should be drawn from: a single trim
+/// segment's own span if one is set (sampling outside it would measure
+/// footage that won't end up in the output), or the whole probed duration
+/// otherwise.
+fn resolve_sample_region(segments: &[TrimSegment], source_duration: f64) -> (f64, f64) {
+    if let [seg] = segments {
+        let start = if seg.start.is_empty() {
+            0.0
+        } else {
+            parse_time(&seg.start).unwrap_or(0.0)
+        };
+        let end = if seg.end.is_empty() {
+            source_duration
+        } else {
+            parse_time(&seg.end).unwrap_or(source_duration)
+        };
+        if end > start {
+            return (start, end);
+        }
+    }
+    (0.0, source_duration)
+}
+
+/// Picks `sample_count` windows of up to `window_secs` long, spread evenly
+/// across the interior of `region_start..region_end` (never flush against
+/// either edge, since the very start/end of a clip is often atypical —
+/// fade-ins, title cards). Each window is clamped to stay inside the region,
+/// and shrinks instead of going out of bounds for a region shorter than
+/// `window_secs`.
+fn sample_windows(
+    region_start: f64,
+    region_end: f64,
+    sample_count: u32,
+    window_secs: f64,
+) -> Vec<(f64, f64)> {
+    let region_len = (region_end - region_start).max(0.0);
+    if region_len <= 0.0 || sample_count == 0 {
+        return Vec::new();
+    }
+    let window_secs = window_secs.min(region_len);
+
+    (1..=sample_count)
+        .map(|i| {
+            let fraction = i as f64 / (sample_count as f64 + 1.0);
+            let center = region_start + region_len * fraction;
+            let start = (center - window_secs / 2.0)
+                .max(region_start)
+                .min(region_end - window_secs);
+            (start, window_secs)
+        })
+        .collect()
+}
+
+fn sample_estimate_cache_dir(app: &AppHandle) -> Result<PathBuf, ConversionError> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join("sample_estimates"))
+        .map_err(|e| ConversionError::Shell(e.to_string()))
+}
+
+/// Encodes a single sample window to a temp file with `config`'s real
+/// settings and returns its measured bitrate in kbps (total output
+/// size/duration, so it naturally folds in audio and container overhead
+/// rather than just the video stream). Registers the spawned process in
+/// `jobs` under `id` for the duration of the run so a cancellation lands on
+/// the right one.
+async fn encode_and_measure_sample(
+    app: &AppHandle,
+    jobs: &SampleEstimateJobs,
+    id: &str,
+    file_path: &str,
+    config: &ConversionConfig,
+    output_path: &PathBuf,
+    window_start: f64,
+    window_len: f64,
+) -> Result<f64, ConversionError> {
+    let mut sample_config = config.clone();
+    sample_config.accurate_trim = true;
+    sample_config.segments = vec![TrimSegment {
+        start: format_timestamp(window_start),
+        end: format_timestamp(window_start + window_len),
+    }];
+
+    let args = build_ffmpeg_args(
+        file_path,
+        &output_path.to_string_lossy(),
+        &sample_config,
+        None,
+        &[],
+    );
+
+    let cmd = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .args(args);
+
+    let (mut rx, child) = cmd
+        .spawn()
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+    jobs.0.lock().unwrap().insert(id.to_string(), child);
+
+    let mut exit_code = None;
+    while let Some(event) = rx.recv().await {
+        if let CommandEvent::Terminated(payload) = event {
+            exit_code = payload.code;
+        }
+    }
+    let cancelled = jobs.0.lock().unwrap().remove(id).is_none();
+
+    if cancelled {
+        return Err(ConversionError::Cancelled(id.to_string()));
+    }
+    if exit_code != Some(0) || !output_path.is_file() {
+        return Err(ConversionError::Shell(
+            "ffmpeg failed to encode an estimate sample".to_string(),
+        ));
+    }
+
+    let size_bytes = std::fs::metadata(output_path).map(|m| m.len()).unwrap_or(0);
+    Ok(size_bytes as f64 * 8.0 / 1000.0 / window_len)
+}
+
+/// Estimates `file_path`'s output size under `config` by actually encoding
+/// `sample_count` short windows and extrapolating their measured bitrate to
+/// the full, trim-aware duration, rather than reading it off a codec's
+/// quality curve (see `estimation::estimate_output_size_bytes`). Pass the
+/// same `id` to [`cancel_estimate_output_accurate`] to stop it early.
+#[tauri::command]
+pub(crate) async fn estimate_output_accurate(
+    app: AppHandle,
+    jobs: tauri::State<'_, SampleEstimateJobs>,
+    id: String,
+    file_path: String,
+    config: ConversionConfig,
+    sample_count: u32,
+) -> Result<SampleEstimateResult, ConversionError> {
+    let metadata = probe_media(app.clone(), file_path.clone()).await?;
+    let source_duration = metadata
+        .duration
+        .as_deref()
+        .and_then(|d| d.parse::<f64>().ok())
+        .ok_or_else(|| ConversionError::Probe("Could not determine duration".to_string()))?;
+
+    let target_duration = trim_duration(&config)
+        .or_else(|| effective_trim_duration(&config.segments, Some(source_duration)))
+        .unwrap_or(source_duration);
+
+    let (region_start, region_end) = resolve_sample_region(&config.segments, source_duration);
+    let windows = sample_windows(region_start, region_end, sample_count, SAMPLE_WINDOW_SECS);
+    if windows.is_empty() {
+        return Err(ConversionError::InvalidInput(
+            "File is too short to sample".to_string(),
+        ));
+    }
+
+    let cache_dir = sample_estimate_cache_dir(&app)?;
+    std::fs::create_dir_all(&cache_dir).map_err(|e| {
+        ConversionError::Shell(format!("Failed to create sample estimate cache dir: {}", e))
+    })?;
+    let job_dir = cache_dir.join(&id);
+    std::fs::create_dir_all(&job_dir)
+        .map_err(|e| ConversionError::Shell(format!("Failed to create sample dir: {}", e)))?;
+
+    let mut per_sample_bitrates_kbps = Vec::with_capacity(windows.len());
+    for (index, (window_start, window_len)) in windows.iter().enumerate() {
+        let output_path = job_dir.join(format!("sample_{}.{}", index, config.container));
+        let result = encode_and_measure_sample(
+            &app,
+            &jobs,
+            &id,
+            &file_path,
+            &config,
+            &output_path,
+            *window_start,
+            *window_len,
+        )
+        .await;
+        let _ = std::fs::remove_file(&output_path);
+
+        match result {
+            Ok(bitrate_kbps) => per_sample_bitrates_kbps.push(bitrate_kbps),
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&job_dir);
+                return Err(e);
+            }
+        }
+    }
+    let _ = std::fs::remove_dir_all(&job_dir);
+
+    let average_kbps =
+        per_sample_bitrates_kbps.iter().sum::<f64>() / per_sample_bitrates_kbps.len() as f64;
+    let size_bytes = (average_kbps * 1000.0 / 8.0 * target_duration.max(0.0)) as u64;
+
+    Ok(SampleEstimateResult {
+        size_bytes,
+        confidence: confidence_from_samples(&per_sample_bitrates_kbps),
+        per_sample_bitrates_kbps,
+    })
+}
+
+/// Kills the in-flight `estimate_output_accurate` run registered under `id`,
+/// if any is still running; a no-op if it already finished or was never
+/// started.
+#[tauri::command]
+pub(crate) fn cancel_estimate_output_accurate(
+    jobs: tauri::State<'_, SampleEstimateJobs>,
+    id: String,
+) -> Result<(), ConversionError> {
+    if let Some(child) = jobs.0.lock().unwrap().remove(&id) {
+        let _ = child.kill();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_sample_region_whole_file_without_segments() {
+        assert_eq!(resolve_sample_region(&[], 100.0), (0.0, 100.0));
+    }
+
+    #[test]
+    fn test_resolve_sample_region_uses_single_segment_span() {
+        let segments = vec![TrimSegment {
+            start: "00:00:10".to_string(),
+            end: "00:00:40".to_string(),
+        }];
+        assert_eq!(resolve_sample_region(&segments, 100.0), (10.0, 40.0));
+    }
+
+    #[test]
+    fn test_resolve_sample_region_falls_back_for_multiple_segments() {
+        let segments = vec![
+            TrimSegment {
+                start: "00:00:10".to_string(),
+                end: "00:00:20".to_string(),
+            },
+            TrimSegment {
+                start: "00:00:30".to_string(),
+                end: "00:00:40".to_string(),
+            },
+        ];
+        assert_eq!(resolve_sample_region(&segments, 100.0), (0.0, 100.0));
+    }
+
+    #[test]
+    fn test_sample_windows_spreads_across_interior() {
+        let windows = sample_windows(0.0, 100.0, 3, 5.0);
+        assert_eq!(windows.len(), 3);
+        for (start, len) in &windows {
+            assert_eq!(*len, 5.0);
+            assert!(*start >= 0.0 && *start + len <= 100.0);
+        }
+        assert!(windows[0].0 < windows[1].0);
+        assert!(windows[1].0 < windows[2].0);
+    }
+
+    #[test]
+    fn test_sample_windows_shrinks_for_short_regions() {
+        let windows = sample_windows(0.0, 2.0, 1, 5.0);
+        assert_eq!(windows, vec![(0.0, 2.0)]);
+    }
+
+    #[test]
+    fn test_sample_windows_empty_for_zero_length_region() {
+        assert!(sample_windows(10.0, 10.0, 3, 5.0).is_empty());
+    }
+
+    #[test]
+    fn test_confidence_from_samples_high_when_uniform() {
+        let bitrates = vec![4000.0, 4010.0, 3990.0];
+        assert_eq!(confidence_from_samples(&bitrates), EstimateConfidence::High);
+    }
+
+    #[test]
+    fn test_confidence_from_samples_low_when_scattered() {
+        let bitrates = vec![1000.0, 4000.0, 9000.0];
+        assert_eq!(confidence_from_samples(&bitrates), EstimateConfidence::Low);
+    }
+
+    #[test]
+    fn test_confidence_from_samples_low_with_a_single_sample() {
+        assert_eq!(confidence_from_samples(&[4000.0]), EstimateConfidence::Low);
+    }
+}
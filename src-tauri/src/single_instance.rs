@@ -0,0 +1,98 @@
+//! Enforces a single running instance of Frame. When a second launch is
+//! detected (e.g. the user double-clicked another video file while Frame was
+//! already open), its file arguments are forwarded here instead of spawning a
+//! second `ConversionManager`: the primary instance focuses its window and
+//! emits `files-opened` so the frontend can queue the forwarded paths, or
+//! stashes them for `take_pending_open_files` if the frontend isn't ready yet.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use tauri::AppHandle;
+
+/// Extensions this build actually knows how to convert; anything else in a
+/// forwarded argument list is a flag or an unrelated file we shouldn't
+/// silently queue. Includes the audio-only containers `is_audio_only_container`
+/// recognizes, since audio conversion is just as much a baseline capability
+/// as video.
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "mov", "avi", "webm", "m4v", "wmv", "flv", "mpg", "mpeg", "ts", "m2ts", "3gp",
+    "mp3", "wav", "flac", "aac", "m4a",
+];
+
+pub(crate) fn has_media_extension(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Picks the plausible media file paths out of a forwarded argv, in order,
+/// with duplicates removed. `argv[0]` (the executable path) is always
+/// skipped; anything starting with `-` is treated as a flag, not a file.
+pub(crate) fn extract_media_file_args(argv: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    argv.iter()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .filter(|arg| has_media_extension(arg))
+        .filter(|arg| seen.insert((*arg).clone()))
+        .cloned()
+        .collect()
+}
+
+/// Handles a forwarded launch from a second instance: validates and
+/// deduplicates its file arguments against the filesystem, then routes them
+/// through `pending_open_files::dispatch`, which forwards them to the
+/// frontend if it's ready or stashes them for later otherwise.
+pub(crate) fn handle_forwarded_launch(app: &AppHandle, argv: Vec<String>) {
+    let mut seen = HashSet::new();
+    let paths: Vec<String> = extract_media_file_args(&argv)
+        .into_iter()
+        .filter_map(|path| std::fs::canonicalize(&path).ok())
+        .filter(|path| path.is_file())
+        .filter(|path| seen.insert(path.clone()))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+
+    crate::pending_open_files::dispatch(app, paths);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_extract_media_file_args_skips_executable_path() {
+        let result = extract_media_file_args(&args(&["/usr/bin/frame", "clip.mp4"]));
+        assert_eq!(result, vec!["clip.mp4".to_string()]);
+    }
+
+    #[test]
+    fn test_extract_media_file_args_ignores_flags() {
+        let result = extract_media_file_args(&args(&["frame", "--flag", "clip.mkv"]));
+        assert_eq!(result, vec!["clip.mkv".to_string()]);
+    }
+
+    #[test]
+    fn test_extract_media_file_args_rejects_non_media_extensions() {
+        let result = extract_media_file_args(&args(&["frame", "notes.txt", "clip.mov"]));
+        assert_eq!(result, vec!["clip.mov".to_string()]);
+    }
+
+    #[test]
+    fn test_extract_media_file_args_deduplicates() {
+        let result = extract_media_file_args(&args(&["frame", "clip.mp4", "clip.mp4"]));
+        assert_eq!(result, vec!["clip.mp4".to_string()]);
+    }
+
+    #[test]
+    fn test_extract_media_file_args_is_case_insensitive() {
+        let result = extract_media_file_args(&args(&["frame", "clip.MP4"]));
+        assert_eq!(result, vec!["clip.MP4".to_string()]);
+    }
+}
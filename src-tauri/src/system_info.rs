@@ -0,0 +1,378 @@
+//! Gathers machine info the settings screen uses to suggest sensible
+//! defaults — CPU model, core counts, RAM, OS version, GPU name(s), and free
+//! space on the output volume. Every probe is best-effort: `None` or an
+//! empty `Vec` means "couldn't determine it", not an error, since none of
+//! this is essential to actually running a conversion.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::conversion::{ConversionManager, available_space_bytes};
+
+/// Isolates the platform-specific probes behind a trait, mirroring
+/// [`crate::conversion::PrioritySetter`], so [`build_system_info`] can be
+/// unit-tested against a fake without touching real hardware.
+pub(crate) trait SystemInfoProbe {
+    fn cpu_model(&self) -> Option<String>;
+    /// Best-effort and single-socket-accurate only: counts unique core IDs
+    /// rather than pairing them with a physical/package ID, which is fine
+    /// for the desktops and laptops this app targets but would undercount a
+    /// multi-socket workstation.
+    fn physical_cores(&self) -> Option<usize>;
+    fn total_ram_bytes(&self) -> Option<u64>;
+    /// Device names as reported by the OS, not ffmpeg's hwaccel backend
+    /// names (`cuda`, `vaapi`, ...) — those identify a decode/encode path,
+    /// not a piece of hardware, so they wouldn't be useful in a "your GPU
+    /// is:" line.
+    fn gpu_names(&self) -> Vec<String>;
+}
+
+pub(crate) struct OsSystemInfoProbe;
+
+#[cfg(target_os = "linux")]
+fn parse_cpuinfo_model_name(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("model name"))
+        .and_then(|rest| rest.split(':').nth(1))
+        .map(|s| s.trim().to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn parse_cpuinfo_physical_cores(contents: &str) -> Option<usize> {
+    let core_ids: std::collections::HashSet<&str> = contents
+        .lines()
+        .filter_map(|line| line.strip_prefix("core id"))
+        .filter_map(|rest| rest.split(':').nth(1))
+        .map(str::trim)
+        .collect();
+    (!core_ids.is_empty()).then_some(core_ids.len())
+}
+
+#[cfg(target_os = "linux")]
+fn parse_meminfo_total_bytes(contents: &str) -> Option<u64> {
+    let kb: u64 = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|s| s.parse().ok())?;
+    Some(kb * 1024)
+}
+
+#[cfg(target_os = "linux")]
+fn parse_lspci_gpu_names(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| {
+            let lower = line.to_lowercase();
+            lower.contains("vga compatible controller")
+                || lower.contains("3d controller")
+                || lower.contains("display controller")
+        })
+        .filter_map(|line| {
+            line.split_once(": ")
+                .map(|(_, name)| name.trim().to_string())
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+impl SystemInfoProbe for OsSystemInfoProbe {
+    fn cpu_model(&self) -> Option<String> {
+        parse_cpuinfo_model_name(&std::fs::read_to_string("/proc/cpuinfo").ok()?)
+    }
+
+    fn physical_cores(&self) -> Option<usize> {
+        parse_cpuinfo_physical_cores(&std::fs::read_to_string("/proc/cpuinfo").ok()?)
+    }
+
+    fn total_ram_bytes(&self) -> Option<u64> {
+        parse_meminfo_total_bytes(&std::fs::read_to_string("/proc/meminfo").ok()?)
+    }
+
+    fn gpu_names(&self) -> Vec<String> {
+        std::process::Command::new("lspci")
+            .output()
+            .ok()
+            .map(|output| parse_lspci_gpu_names(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn sysctl_string(name: &str) -> Option<String> {
+    let output = std::process::Command::new("sysctl")
+        .args(["-n", name])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn sysctl_u64(name: &str) -> Option<u64> {
+    sysctl_string(name)?.parse().ok()
+}
+
+#[cfg(target_os = "macos")]
+fn parse_system_profiler_gpu_names(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Chipset Model:"))
+        .map(|s| s.trim().to_string())
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+impl SystemInfoProbe for OsSystemInfoProbe {
+    fn cpu_model(&self) -> Option<String> {
+        sysctl_string("machdep.cpu.brand_string")
+    }
+
+    fn physical_cores(&self) -> Option<usize> {
+        sysctl_u64("hw.physicalcpu").map(|n| n as usize)
+    }
+
+    fn total_ram_bytes(&self) -> Option<u64> {
+        sysctl_u64("hw.memsize")
+    }
+
+    fn gpu_names(&self) -> Vec<String> {
+        std::process::Command::new("system_profiler")
+            .arg("SPDisplaysDataType")
+            .output()
+            .ok()
+            .map(|output| parse_system_profiler_gpu_names(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parses `wmic ... get <Field>` output: a header line followed by one value
+/// per row, used for both the CPU model and GPU name probes.
+#[cfg(target_os = "windows")]
+fn parse_wmic_column(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip(1)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn windows_total_ram_bytes() -> Option<u64> {
+    use windows::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    let mut status = MEMORYSTATUSEX {
+        dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32,
+        ..Default::default()
+    };
+    unsafe { GlobalMemoryStatusEx(&mut status) }.ok()?;
+    Some(status.ullTotalPhys)
+}
+
+#[cfg(target_os = "windows")]
+impl SystemInfoProbe for OsSystemInfoProbe {
+    fn cpu_model(&self) -> Option<String> {
+        command_output("wmic", &["cpu", "get", "Name"])
+            .map(|out| parse_wmic_column(&out))
+            .and_then(|names| names.into_iter().next())
+    }
+
+    fn physical_cores(&self) -> Option<usize> {
+        let out = command_output("wmic", &["cpu", "get", "NumberOfCores"])?;
+        let total: usize = parse_wmic_column(&out)
+            .iter()
+            .filter_map(|s| s.parse().ok())
+            .sum();
+        (total > 0).then_some(total)
+    }
+
+    fn total_ram_bytes(&self) -> Option<u64> {
+        windows_total_ram_bytes()
+    }
+
+    fn gpu_names(&self) -> Vec<String> {
+        command_output("wmic", &["path", "win32_VideoController", "get", "Name"])
+            .map(|out| parse_wmic_column(&out))
+            .unwrap_or_default()
+    }
+}
+
+/// What the settings UI needs to suggest defaults and warn before a heavy
+/// encode is queued on underpowered hardware.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub cpu_model: Option<String>,
+    pub physical_cores: Option<usize>,
+    pub logical_cores: usize,
+    pub total_ram_bytes: Option<u64>,
+    pub os_version: String,
+    pub gpu_names: Vec<String>,
+    /// Free space on the volume backing `output_dir`, or the current working
+    /// directory's volume when no output directory is configured (outputs
+    /// then land next to whatever source file they came from, scattered
+    /// across the filesystem, so there's no single "default" volume to
+    /// report).
+    pub free_space_bytes: Option<u64>,
+}
+
+pub(crate) fn build_system_info(
+    probe: &dyn SystemInfoProbe,
+    output_dir: Option<PathBuf>,
+) -> SystemInfo {
+    let logical_cores = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let free_space_dir = output_dir.or_else(|| std::env::current_dir().ok());
+    let free_space_bytes = free_space_dir.and_then(|dir| available_space_bytes(&dir));
+
+    SystemInfo {
+        cpu_model: probe.cpu_model(),
+        physical_cores: probe.physical_cores(),
+        logical_cores,
+        total_ram_bytes: probe.total_ram_bytes(),
+        os_version: format!(
+            "{} {}",
+            tauri_plugin_os::platform(),
+            tauri_plugin_os::version()
+        ),
+        gpu_names: probe.gpu_names(),
+        free_space_bytes,
+    }
+}
+
+#[tauri::command]
+pub(crate) fn get_system_info(manager: tauri::State<'_, ConversionManager>) -> SystemInfo {
+    let output_dir = manager.current_output_settings().output_directory;
+    build_system_info(&OsSystemInfoProbe, output_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeProbe;
+
+    impl SystemInfoProbe for FakeProbe {
+        fn cpu_model(&self) -> Option<String> {
+            Some("Fake CPU".to_string())
+        }
+
+        fn physical_cores(&self) -> Option<usize> {
+            Some(4)
+        }
+
+        fn total_ram_bytes(&self) -> Option<u64> {
+            Some(16 * 1024 * 1024 * 1024)
+        }
+
+        fn gpu_names(&self) -> Vec<String> {
+            vec!["Fake GPU".to_string()]
+        }
+    }
+
+    #[test]
+    fn test_system_info_probe_trait_is_mockable() {
+        let info = build_system_info(&FakeProbe, None);
+
+        assert_eq!(info.cpu_model.as_deref(), Some("Fake CPU"));
+        assert_eq!(info.physical_cores, Some(4));
+        assert_eq!(info.total_ram_bytes, Some(16 * 1024 * 1024 * 1024));
+        assert_eq!(info.gpu_names, vec!["Fake GPU".to_string()]);
+        assert!(info.logical_cores >= 1);
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpuinfo_model_name() {
+        let sample = "processor\t: 0\nmodel name\t: Intel(R) Core(TM) i7-9700K\ncpu MHz\t: 3600\n";
+        assert_eq!(
+            parse_cpuinfo_model_name(sample).as_deref(),
+            Some("Intel(R) Core(TM) i7-9700K")
+        );
+    }
+
+    #[test]
+    fn test_parse_cpuinfo_physical_cores_counts_unique_core_ids() {
+        let sample = "processor\t: 0\ncore id\t: 0\n\nprocessor\t: 1\ncore id\t: 1\n\nprocessor\t: 2\ncore id\t: 0\n";
+        assert_eq!(parse_cpuinfo_physical_cores(sample), Some(2));
+    }
+
+    #[test]
+    fn test_parse_cpuinfo_physical_cores_returns_none_when_absent() {
+        assert_eq!(parse_cpuinfo_physical_cores("processor\t: 0\n"), None);
+    }
+
+    #[test]
+    fn test_parse_meminfo_total_bytes() {
+        let sample = "MemTotal:       16384000 kB\nMemFree:        2048000 kB\n";
+        assert_eq!(parse_meminfo_total_bytes(sample), Some(16384000 * 1024));
+    }
+
+    #[test]
+    fn test_parse_lspci_gpu_names_matches_display_controllers_only() {
+        let sample = "00:02.0 VGA compatible controller: Intel Corporation UHD Graphics 620\n\
+00:1f.3 Audio device: Intel Corporation Device a348\n\
+01:00.0 3D controller: NVIDIA Corporation GP108M [GeForce MX150]\n";
+
+        let names = parse_lspci_gpu_names(sample);
+
+        assert_eq!(
+            names,
+            vec![
+                "Intel Corporation UHD Graphics 620".to_string(),
+                "NVIDIA Corporation GP108M [GeForce MX150]".to_string(),
+            ]
+        );
+    }
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod macos_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_system_profiler_gpu_names() {
+        let sample = "Graphics/Displays:\n    Intel Iris Pro:\n      Chipset Model: Intel Iris Pro\n      Type: GPU\n";
+        assert_eq!(
+            parse_system_profiler_gpu_names(sample),
+            vec!["Intel Iris Pro".to_string()]
+        );
+    }
+}
+
+#[cfg(all(test, target_os = "windows"))]
+mod windows_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wmic_column_skips_header_and_blank_lines() {
+        let sample = "Name\r\nNVIDIA GeForce RTX 3080\r\n\r\n";
+        assert_eq!(
+            parse_wmic_column(sample),
+            vec!["NVIDIA GeForce RTX 3080".to_string()]
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Mirrors the queue's aggregate progress onto the OS taskbar/dock icon
+//! (Windows taskbar fill, macOS dock progress) using Tauri's cross-platform
+//! `set_progress_bar`, rather than reaching for `ITaskbarList3` directly.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Listener, Manager};
+
+use crate::conversion::QueueProgressSnapshot;
+
+/// Subscribes to `queue-progress` and `conversion-error` and keeps the main
+/// window's progress indicator in sync: filling as the queue advances,
+/// turning red once any task has failed, and clearing once the queue is
+/// empty again. Call once, after the main window is built; if the window
+/// isn't around yet when an event fires (e.g. during the splash phase),
+/// the update is silently skipped rather than panicking.
+pub fn init(app: &AppHandle) {
+    let has_failed = Arc::new(AtomicBool::new(false));
+
+    let has_failed_for_error = Arc::clone(&has_failed);
+    app.listen("conversion-error", move |_event| {
+        has_failed_for_error.store(true, Ordering::SeqCst);
+    });
+
+    let app_handle = app.clone();
+    app.listen("queue-progress", move |event| {
+        let Ok(snapshot) = serde_json::from_str::<QueueProgressSnapshot>(event.payload()) else {
+            return;
+        };
+        let Some(window) = app_handle.get_webview_window("main") else {
+            return;
+        };
+
+        let state = if snapshot.total_tasks == 0 {
+            has_failed.store(false, Ordering::SeqCst);
+            ProgressBarState {
+                status: Some(ProgressBarStatus::None),
+                progress: None,
+            }
+        } else {
+            ProgressBarState {
+                status: Some(if has_failed.load(Ordering::SeqCst) {
+                    ProgressBarStatus::Error
+                } else {
+                    ProgressBarStatus::Normal
+                }),
+                progress: Some(snapshot.percent.round().clamp(0.0, 100.0) as u64),
+            }
+        };
+        let _ = window.set_progress_bar(state);
+    });
+}
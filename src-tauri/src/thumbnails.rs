@@ -0,0 +1,420 @@
+//! Generates and caches file-list thumbnails: a single decoded video frame,
+//! or for audio-only files their embedded cover art, written once under the
+//! app cache dir and reused by path/mtime/timestamp/width until the source
+//! file changes.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::ShellExt;
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+
+use crate::conversion::{ConversionError, probe_media};
+
+/// Total on-disk budget for cached thumbnails before the oldest are evicted.
+const THUMBNAIL_CACHE_MAX_BYTES: u64 = 200 * 1024 * 1024;
+
+fn thumbnail_cache_dir(app: &AppHandle) -> Result<PathBuf, ConversionError> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join("thumbnails"))
+        .map_err(|e| ConversionError::Shell(e.to_string()))
+}
+
+/// Derives the cache filename for a thumbnail request. Keying on `mtime`
+/// (rather than just the path) means a source file edited and re-exported at
+/// the same path invalidates automatically instead of serving a stale frame.
+fn cache_key(file_path: &str, mtime: SystemTime, timestamp_secs: f64, max_width: u32) -> String {
+    let mut hasher = DefaultHasher::new();
+    file_path.hash(&mut hasher);
+    mtime
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+        .hash(&mut hasher);
+    timestamp_secs.to_bits().hash(&mut hasher);
+    max_width.hash(&mut hasher);
+    format!("{:016x}.jpg", hasher.finish())
+}
+
+/// Deletes cached thumbnails oldest-mtime-first until `dir` is back under
+/// [`THUMBNAIL_CACHE_MAX_BYTES`]. Best-effort: a failed listing or removal
+/// just leaves the cache over budget until the next generation call.
+fn evict_oldest_until_under_budget(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut files: Vec<(PathBuf, SystemTime, u64)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            let modified = metadata.modified().ok()?;
+            Some((entry.path(), modified, metadata.len()))
+        })
+        .collect();
+
+    let mut total: u64 = files.iter().map(|(_, _, size)| size).sum();
+    if total <= THUMBNAIL_CACHE_MAX_BYTES {
+        return;
+    }
+
+    files.sort_by_key(|(_, modified, _)| *modified);
+    for (path, _, size) in files {
+        if total <= THUMBNAIL_CACHE_MAX_BYTES {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(size);
+        }
+    }
+}
+
+/// Grabs a single frame at `timestamp_secs`, scaled to `max_width` wide, and
+/// writes it to `output_path`.
+async fn extract_video_frame(
+    app: &AppHandle,
+    file_path: &str,
+    timestamp_secs: f64,
+    max_width: u32,
+    output_path: &Path,
+) -> Result<(), ConversionError> {
+    let cmd = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+    let output = cmd
+        .args([
+            "-ss",
+            &timestamp_secs.to_string(),
+            "-i",
+            file_path,
+            "-frames:v",
+            "1",
+            "-vf",
+            &format!("scale={}:-2", max_width),
+            "-y",
+            &output_path.to_string_lossy(),
+        ])
+        .output()
+        .await
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+
+    if output.status.success() && output_path.is_file() {
+        Ok(())
+    } else {
+        Err(ConversionError::Shell(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// Pulls an audio file's embedded cover art (the `attached_pic` stream) out
+/// as-is, without re-encoding or scaling: album art is usually already a
+/// reasonable thumbnail size.
+async fn extract_cover_art(
+    app: &AppHandle,
+    file_path: &str,
+    output_path: &Path,
+) -> Result<(), ConversionError> {
+    let cmd = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+    let output = cmd
+        .args([
+            "-i",
+            file_path,
+            "-an",
+            "-c:v",
+            "copy",
+            "-y",
+            &output_path.to_string_lossy(),
+        ])
+        .output()
+        .await
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+
+    if output.status.success() && output_path.is_file() {
+        Ok(())
+    } else {
+        Err(ConversionError::InvalidInput(
+            "File has no video and no embedded cover art".to_string(),
+        ))
+    }
+}
+
+/// Generates (or returns the already-cached) thumbnail for `file_path` at
+/// `timestamp_secs`, scaled to `max_width` pixels wide, so the file list can
+/// show a preview without the frontend re-rendering one on every refresh.
+/// Audio-only files fall back to their embedded cover art, ignoring
+/// `timestamp_secs`/`max_width`; files with neither a video stream nor
+/// embedded art return [`ConversionError::InvalidInput`].
+#[tauri::command]
+pub(crate) async fn generate_thumbnail(
+    app: AppHandle,
+    file_path: String,
+    timestamp_secs: f64,
+    max_width: u32,
+) -> Result<String, ConversionError> {
+    let mtime = std::fs::metadata(&file_path)
+        .and_then(|m| m.modified())
+        .map_err(|e| ConversionError::InvalidInput(format!("Cannot read {}: {}", file_path, e)))?;
+
+    let cache_dir = thumbnail_cache_dir(&app)?;
+    std::fs::create_dir_all(&cache_dir).map_err(|e| {
+        ConversionError::Shell(format!("Failed to create thumbnail cache dir: {}", e))
+    })?;
+
+    let output_path = cache_dir.join(cache_key(&file_path, mtime, timestamp_secs, max_width));
+    if output_path.is_file() {
+        return Ok(output_path.to_string_lossy().to_string());
+    }
+
+    let metadata = probe_media(app.clone(), file_path.clone()).await?;
+    if metadata.video_codec.is_some() {
+        extract_video_frame(&app, &file_path, timestamp_secs, max_width, &output_path).await?;
+    } else {
+        extract_cover_art(&app, &file_path, &output_path).await?;
+    }
+
+    evict_oldest_until_under_budget(&cache_dir);
+
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// Frames closer together than this aren't useful on a filmstrip and just
+/// waste an ffmpeg decode; very short clips get fewer frames than requested
+/// rather than duplicate ones.
+const MIN_FILMSTRIP_FRAME_SPACING_SECS: f64 = 0.1;
+
+/// Managed state tracking in-flight `generate_filmstrip` ffmpeg processes by
+/// caller-supplied abort id, so `cancel_filmstrip` can kill one if the user
+/// closes the trim dialog before it finishes.
+#[derive(Default)]
+pub(crate) struct FilmstripJobs(Mutex<HashMap<String, CommandChild>>);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FilmstripFrame {
+    pub path: String,
+    pub timestamp_secs: f64,
+}
+
+/// Picks `count` timestamps evenly spaced across `duration_secs`, clamped
+/// down when that would pack frames closer than
+/// [`MIN_FILMSTRIP_FRAME_SPACING_SECS`] (very short clips).
+fn evenly_spaced_timestamps(duration_secs: f64, count: u32) -> Vec<f64> {
+    if duration_secs <= 0.0 || count == 0 {
+        return Vec::new();
+    }
+
+    let max_frames = (duration_secs / MIN_FILMSTRIP_FRAME_SPACING_SECS).floor() as u32 + 1;
+    let count = count.min(max_frames).max(1);
+    if count == 1 {
+        return vec![0.0];
+    }
+
+    (0..count)
+        .map(|i| duration_secs * i as f64 / (count - 1) as f64)
+        .collect()
+}
+
+/// Runs a single ffmpeg pass that samples the input at an even `fps` (so the
+/// frames land close to `timestamps`) and writes them out as a numbered
+/// sequence under `output_dir`. Registers the spawned process in `jobs` under
+/// `abort_id` for the duration of the run so `cancel_filmstrip` can kill it.
+async fn extract_filmstrip_frames(
+    app: &AppHandle,
+    jobs: &FilmstripJobs,
+    abort_id: &str,
+    file_path: &str,
+    timestamps: &[f64],
+    height: u32,
+    duration_secs: f64,
+    output_dir: &Path,
+) -> Result<Vec<PathBuf>, ConversionError> {
+    let count = timestamps.len() as u32;
+    let fps = count as f64 / duration_secs.max(MIN_FILMSTRIP_FRAME_SPACING_SECS);
+    let pattern = output_dir.join("frame_%03d.jpg");
+
+    let cmd = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .args([
+            "-i",
+            file_path,
+            "-vf",
+            &format!("fps={},scale=-2:{}", fps, height),
+            "-vsync",
+            "vfr",
+            "-frames:v",
+            &count.to_string(),
+            "-y",
+            &pattern.to_string_lossy(),
+        ]);
+
+    let (mut rx, child) = cmd
+        .spawn()
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+    jobs.0.lock().unwrap().insert(abort_id.to_string(), child);
+
+    let mut exit_code = None;
+    while let Some(event) = rx.recv().await {
+        if let CommandEvent::Terminated(payload) = event {
+            exit_code = payload.code;
+        }
+    }
+    let cancelled = jobs.0.lock().unwrap().remove(abort_id).is_none();
+
+    if cancelled {
+        return Err(ConversionError::Cancelled(abort_id.to_string()));
+    }
+    if exit_code != Some(0) {
+        return Err(ConversionError::Shell(
+            "ffmpeg exited with a non-zero status while building the filmstrip".to_string(),
+        ));
+    }
+
+    let mut paths = Vec::with_capacity(count as usize);
+    for i in 1..=count {
+        let frame_path = output_dir.join(format!("frame_{:03}.jpg", i));
+        if frame_path.is_file() {
+            paths.push(frame_path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Generates a row of `count` evenly spaced frames across `file_path`'s
+/// duration, scaled to `height` tall, for the trim timeline. Writes them to
+/// the thumbnail cache dir and returns their paths alongside the timestamp
+/// each one was taken at. Pass the same `abort_id` to [`cancel_filmstrip`] to
+/// stop the underlying ffmpeg process if the trim dialog closes early.
+#[tauri::command]
+pub(crate) async fn generate_filmstrip(
+    app: AppHandle,
+    jobs: tauri::State<'_, FilmstripJobs>,
+    abort_id: String,
+    file_path: String,
+    count: u32,
+    height: u32,
+) -> Result<Vec<FilmstripFrame>, ConversionError> {
+    let metadata = probe_media(app.clone(), file_path.clone()).await?;
+    let duration_secs = metadata
+        .duration
+        .as_deref()
+        .and_then(|d| d.parse::<f64>().ok())
+        .ok_or_else(|| ConversionError::Probe("Could not determine duration".to_string()))?;
+
+    let timestamps = evenly_spaced_timestamps(duration_secs, count);
+    if timestamps.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let cache_dir = thumbnail_cache_dir(&app)?;
+    let job_dir = cache_dir.join(format!("filmstrip_{}", abort_id));
+    std::fs::create_dir_all(&job_dir)
+        .map_err(|e| ConversionError::Shell(format!("Failed to create filmstrip dir: {}", e)))?;
+
+    let paths = extract_filmstrip_frames(
+        &app,
+        &jobs,
+        &abort_id,
+        &file_path,
+        &timestamps,
+        height,
+        duration_secs,
+        &job_dir,
+    )
+    .await?;
+
+    evict_oldest_until_under_budget(&cache_dir);
+
+    Ok(paths
+        .into_iter()
+        .zip(timestamps)
+        .map(|(path, timestamp_secs)| FilmstripFrame {
+            path: path.to_string_lossy().to_string(),
+            timestamp_secs,
+        })
+        .collect())
+}
+
+/// Kills the in-flight `generate_filmstrip` run registered under `abort_id`,
+/// if any is still running; a no-op if it already finished or was never
+/// started.
+#[tauri::command]
+pub(crate) fn cancel_filmstrip(
+    jobs: tauri::State<'_, FilmstripJobs>,
+    abort_id: String,
+) -> Result<(), ConversionError> {
+    if let Some(child) = jobs.0.lock().unwrap().remove(&abort_id) {
+        let _ = child.kill();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key_changes_with_mtime() {
+        let a = cache_key("clip.mp4", UNIX_EPOCH, 5.0, 320);
+        let b = cache_key(
+            "clip.mp4",
+            UNIX_EPOCH + std::time::Duration::from_secs(1),
+            5.0,
+            320,
+        );
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_cache_key_changes_with_timestamp_and_width() {
+        let base = cache_key("clip.mp4", UNIX_EPOCH, 5.0, 320);
+        assert_ne!(base, cache_key("clip.mp4", UNIX_EPOCH, 6.0, 320));
+        assert_ne!(base, cache_key("clip.mp4", UNIX_EPOCH, 5.0, 640));
+    }
+
+    #[test]
+    fn test_cache_key_is_deterministic() {
+        let a = cache_key("clip.mp4", UNIX_EPOCH, 5.0, 320);
+        let b = cache_key("clip.mp4", UNIX_EPOCH, 5.0, 320);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_evenly_spaced_timestamps_spans_full_duration() {
+        let timestamps = evenly_spaced_timestamps(10.0, 5);
+        assert_eq!(timestamps, vec![0.0, 2.5, 5.0, 7.5, 10.0]);
+    }
+
+    #[test]
+    fn test_evenly_spaced_timestamps_degrades_for_short_clips() {
+        let timestamps = evenly_spaced_timestamps(0.2, 10);
+        assert!(timestamps.len() < 10);
+        assert!(timestamps.len() >= 1);
+    }
+
+    #[test]
+    fn test_evenly_spaced_timestamps_handles_zero_duration() {
+        assert!(evenly_spaced_timestamps(0.0, 5).is_empty());
+    }
+
+    #[test]
+    fn test_evenly_spaced_timestamps_handles_single_frame() {
+        assert_eq!(evenly_spaced_timestamps(10.0, 1), vec![0.0]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Audio waveform peaks for the trim UI: decodes a single audio stream down
+//! to a low sample-rate mono PCM stream and reduces it to a fixed-size
+//! min/max envelope cheap enough to draw on every frame of a drag.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+use tauri_plugin_shell::process::CommandEvent;
+
+use crate::conversion::ConversionError;
+
+/// Ample time for even a very long track to decode at 8kHz mono; a stream
+/// that can't be decoded at all (bad map index, corrupt file) fails well
+/// before this, so hitting it means ffmpeg is genuinely stuck.
+const AUDIO_DECODE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Managed state caching the last `get_audio_peaks` result per
+/// path/mtime/track/resolution, so scrubbing the trim UI doesn't re-decode
+/// the whole file on every redraw.
+#[derive(Default)]
+pub(crate) struct AudioPeaksCache(Mutex<HashMap<String, Vec<f32>>>);
+
+fn peaks_cache_key(file_path: &str, mtime: SystemTime, track_index: u32, samples: u32) -> String {
+    let mut hasher = DefaultHasher::new();
+    file_path.hash(&mut hasher);
+    mtime
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+        .hash(&mut hasher);
+    track_index.hash(&mut hasher);
+    samples.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Reduces a run of decoded mono i16 PCM samples into exactly `bin_count`
+/// evenly sized min/max pairs, normalized to -1.0..1.0. Bin sizes differ by
+/// at most one sample when `samples.len()` isn't evenly divisible by
+/// `bin_count`; with fewer samples than bins, each bin holds a single sample.
+fn reduce_samples_to_peaks(samples: &[i16], bin_count: usize) -> Vec<f32> {
+    if bin_count == 0 || samples.is_empty() {
+        return Vec::new();
+    }
+
+    let len = samples.len();
+    let mut peaks = Vec::with_capacity(bin_count * 2);
+
+    for i in 0..bin_count {
+        let start = i * len / bin_count;
+        let end = ((i + 1) * len / bin_count).max(start + 1);
+        let chunk = &samples[start..end];
+        let min = chunk.iter().copied().min().unwrap_or(0);
+        let max = chunk.iter().copied().max().unwrap_or(0);
+        peaks.push(min as f32 / i16::MAX as f32);
+        peaks.push(max as f32 / i16::MAX as f32);
+    }
+
+    peaks
+}
+
+/// Streams `track_index` out of `file_path` as raw mono 8kHz s16le PCM,
+/// reading the child's stdout incrementally rather than buffering the whole
+/// decode in one `.output()` call, so large files don't stall behind a
+/// single giant read.
+async fn decode_mono_pcm(
+    app: &AppHandle,
+    file_path: &str,
+    track_index: u32,
+) -> Result<Vec<i16>, ConversionError> {
+    let cmd = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .args([
+            "-i",
+            file_path,
+            "-map",
+            &format!("0:a:{}", track_index),
+            "-f",
+            "s16le",
+            "-ac",
+            "1",
+            "-ar",
+            "8000",
+            "-",
+        ]);
+
+    let (mut rx, child) = cmd
+        .spawn()
+        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+
+    let read_to_completion = async {
+        let mut raw = Vec::new();
+        let mut stderr_tail = String::new();
+        let mut exit_code = None;
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(bytes) => raw.extend_from_slice(&bytes),
+                CommandEvent::Stderr(bytes) => {
+                    let line = String::from_utf8_lossy(&bytes);
+                    if !line.trim().is_empty() {
+                        stderr_tail = line.trim().to_string();
+                    }
+                }
+                CommandEvent::Terminated(payload) => exit_code = payload.code,
+                _ => {}
+            }
+        }
+        (raw, exit_code, stderr_tail)
+    };
+
+    let (raw, exit_code, stderr_tail) =
+        tokio::time::timeout(AUDIO_DECODE_TIMEOUT, read_to_completion)
+            .await
+            .map_err(|_| {
+                let _ = child.kill();
+                ConversionError::Probe("Timed out decoding audio for waveform".to_string())
+            })?;
+
+    if exit_code != Some(0) {
+        return Err(ConversionError::Probe(if stderr_tail.is_empty() {
+            "ffmpeg failed to decode the selected audio stream".to_string()
+        } else {
+            stderr_tail
+        }));
+    }
+
+    Ok(raw
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect())
+}
+
+/// Returns `samples` min/max peak pairs (flattened as `[min0, max0, min1,
+/// max1, ...]`, normalized to -1.0..1.0) for `track_index` of `file_path`,
+/// for drawing a compact waveform overview in the trim UI. Cached by path,
+/// mtime, track and requested resolution.
+#[tauri::command]
+pub(crate) async fn get_audio_peaks(
+    app: AppHandle,
+    cache: tauri::State<'_, AudioPeaksCache>,
+    file_path: String,
+    track_index: u32,
+    samples: u32,
+) -> Result<Vec<f32>, ConversionError> {
+    let mtime = std::fs::metadata(&file_path)
+        .and_then(|m| m.modified())
+        .map_err(|e| ConversionError::InvalidInput(format!("Cannot read {}: {}", file_path, e)))?;
+
+    let key = peaks_cache_key(&file_path, mtime, track_index, samples);
+    if let Some(cached) = cache.0.lock().unwrap().get(&key).cloned() {
+        return Ok(cached);
+    }
+
+    let pcm = decode_mono_pcm(&app, &file_path, track_index).await?;
+    let peaks = reduce_samples_to_peaks(&pcm, samples as usize);
+
+    cache.0.lock().unwrap().insert(key, peaks.clone());
+    Ok(peaks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reduce_samples_to_peaks_produces_min_max_pairs() {
+        let samples = [0, 100, -200, 50, -10, 300, -400, 20];
+        let peaks = reduce_samples_to_peaks(&samples, 2);
+        assert_eq!(peaks.len(), 4);
+        assert_eq!(peaks[0], -200.0 / i16::MAX as f32);
+        assert_eq!(peaks[1], 100.0 / i16::MAX as f32);
+        assert_eq!(peaks[2], -400.0 / i16::MAX as f32);
+        assert_eq!(peaks[3], 300.0 / i16::MAX as f32);
+    }
+
+    #[test]
+    fn test_reduce_samples_to_peaks_handles_empty_input() {
+        assert!(reduce_samples_to_peaks(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn test_reduce_samples_to_peaks_handles_zero_bins() {
+        assert!(reduce_samples_to_peaks(&[1, 2, 3], 0).is_empty());
+    }
+
+    #[test]
+    fn test_reduce_samples_to_peaks_handles_uneven_division() {
+        let samples: Vec<i16> = (0..10).collect();
+        let peaks = reduce_samples_to_peaks(&samples, 3);
+        assert_eq!(peaks.len(), 6);
+    }
+}
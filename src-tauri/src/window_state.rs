@@ -0,0 +1,193 @@
+//! Persists the main window's size, position, and maximized state across
+//! launches via the store plugin, restoring it right after the window is
+//! built (but before it's shown) with a sanity check that the saved
+//! rectangle still lands on a connected monitor, and saving again on
+//! move/resize (debounced) or close.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, PhysicalPosition, PhysicalSize, WebviewWindow, WindowEvent};
+use tauri_plugin_store::StoreExt;
+
+const STORE_FILE: &str = "window-state.json";
+const STORE_KEY: &str = "main";
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+const MIN_WIDTH: u32 = 1200;
+const MIN_HEIGHT: u32 = 800;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct WindowState {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+}
+
+fn load(app: &AppHandle) -> Option<WindowState> {
+    let store = app.store(STORE_FILE).ok()?;
+    serde_json::from_value(store.get(STORE_KEY)?).ok()
+}
+
+fn save(app: &AppHandle, state: WindowState) {
+    let Ok(store) = app.store(STORE_FILE) else {
+        return;
+    };
+    let Ok(value) = serde_json::to_value(state) else {
+        return;
+    };
+    store.set(STORE_KEY, value);
+    let _ = store.save();
+}
+
+/// True if `state`'s outer rectangle overlaps at least one currently
+/// connected monitor, so a display that's been unplugged since the last run
+/// can't strand the window somewhere off-screen.
+fn intersects_a_monitor(window: &WebviewWindow, state: &WindowState) -> bool {
+    let Ok(monitors) = window.available_monitors() else {
+        return false;
+    };
+    let (left, top) = (state.x, state.y);
+    let (right, bottom) = (
+        state.x.saturating_add(state.width as i32),
+        state.y.saturating_add(state.height as i32),
+    );
+    monitors.iter().any(|monitor| {
+        let pos = monitor.position();
+        let size = monitor.size();
+        let (mon_left, mon_top) = (pos.x, pos.y);
+        let mon_right = pos.x.saturating_add(size.width as i32);
+        let mon_bottom = pos.y.saturating_add(size.height as i32);
+        left < mon_right && right > mon_left && top < mon_bottom && bottom > mon_top
+    })
+}
+
+/// The min-size constraint set on the `WebviewWindowBuilder` must still win
+/// over a corrupt (or just very old, pre-min-size) saved size smaller than
+/// it.
+fn clamp_to_minimum(mut state: WindowState) -> WindowState {
+    state.width = state.width.max(MIN_WIDTH);
+    state.height = state.height.max(MIN_HEIGHT);
+    state
+}
+
+fn capture(window: &WebviewWindow) -> Option<WindowState> {
+    let position = window.outer_position().ok()?;
+    let size = window.outer_size().ok()?;
+    let maximized = window.is_maximized().ok()?;
+    Some(WindowState {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized,
+    })
+}
+
+/// Reads the saved window state, if any, discarding it in favor of `None`
+/// when it no longer overlaps a connected monitor.
+fn restorable_state(app: &AppHandle, window: &WebviewWindow) -> Option<WindowState> {
+    let state = clamp_to_minimum(load(app)?);
+    intersects_a_monitor(window, &state).then_some(state)
+}
+
+/// Applies the saved state (if any and still valid) to `window`, then wires
+/// up debounced persistence for future moves/resizes plus an immediate save
+/// on close. Call once, right after the window is built and before it's
+/// shown. Returns the state actually applied, so callers (e.g. the splash
+/// window) can position themselves relative to it.
+pub(crate) fn restore_and_track(app: &AppHandle, window: &WebviewWindow) -> Option<WindowState> {
+    let restored = restorable_state(app, window);
+    if let Some(state) = restored {
+        let _ = window.set_size(PhysicalSize::new(state.width, state.height));
+        let _ = window.set_position(PhysicalPosition::new(state.x, state.y));
+        if state.maximized {
+            let _ = window.maximize();
+        }
+    }
+
+    let generation = Arc::new(AtomicU64::new(0));
+    let app_handle = app.clone();
+    let tracked_window = window.clone();
+
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+            let my_generation = generation.fetch_add(1, Ordering::SeqCst) + 1;
+            let generation = generation.clone();
+            let app_handle = app_handle.clone();
+            let tracked_window = tracked_window.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(SAVE_DEBOUNCE).await;
+                if generation.load(Ordering::SeqCst) == my_generation {
+                    if let Some(state) = capture(&tracked_window) {
+                        save(&app_handle, state);
+                    }
+                }
+            });
+        }
+        WindowEvent::CloseRequested { .. } => {
+            if let Some(state) = capture(&tracked_window) {
+                save(&app_handle, state);
+            }
+        }
+        _ => {}
+    });
+
+    restored
+}
+
+/// Where the splash window should sit so it's centered over the restored (or
+/// default) main window rather than wherever the OS decides to put it.
+pub(crate) fn centered_splash_position(
+    main: &WindowState,
+    splash_width: f64,
+    splash_height: f64,
+) -> PhysicalPosition<i32> {
+    let x = main.x as f64 + (main.width as f64 - splash_width) / 2.0;
+    let y = main.y as f64 + (main.height as f64 - splash_height) / 2.0;
+    PhysicalPosition::new(x as i32, y as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(x: i32, y: i32, width: u32, height: u32) -> WindowState {
+        WindowState {
+            x,
+            y,
+            width,
+            height,
+            maximized: false,
+        }
+    }
+
+    #[test]
+    fn test_clamp_to_minimum_leaves_valid_sizes_alone() {
+        let s = clamp_to_minimum(state(10, 10, 1600, 900));
+        assert_eq!((s.width, s.height), (1600, 900));
+    }
+
+    #[test]
+    fn test_clamp_to_minimum_enforces_floor_on_corrupt_size() {
+        let s = clamp_to_minimum(state(10, 10, 50, 50));
+        assert_eq!((s.width, s.height), (MIN_WIDTH, MIN_HEIGHT));
+    }
+
+    #[test]
+    fn test_clamp_to_minimum_only_raises_the_dimension_that_is_too_small() {
+        let s = clamp_to_minimum(state(0, 0, 50, 1500));
+        assert_eq!((s.width, s.height), (MIN_WIDTH, 1500));
+    }
+
+    #[test]
+    fn test_centered_splash_position() {
+        let main = state(100, 200, 1200, 800);
+        let pos = centered_splash_position(&main, 300.0, 300.0);
+        assert_eq!(pos.x, 100 + (1200 - 300) / 2);
+        assert_eq!(pos.y, 200 + (800 - 300) / 2);
+    }
+}